@@ -1,55 +1,149 @@
+use std::borrow::Cow;
 use std::collections::hash_map::DefaultHasher;
 use std::collections::{BTreeMap, BTreeSet};
 use std::hash::{Hash, Hasher};
 use std::ops::Deref;
+use std::path::PathBuf;
 use std::slice::Iter;
 use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
 
 use egui::{Context, Key, TextBuffer, Ui};
-use tracing::{error, info};
+use tracing::{info, warn};
 
-use crate::app::interview::InterviewSwiper;
+use crate::app::codebook_import::{CodebookImportAction, CodebookImportMode, PendingCodebook};
+use crate::app::consensus::{Comparison, ComparisonAction, Review};
+use crate::app::export::{Columns, ExportReport, InterviewExportOptions};
+use crate::app::file_upload::UploadedFile;
+use crate::app::interview::{InterviewSwiper, Restructure};
+use crate::app::notifications::Notifications;
 use crate::app::number_selector::number_changer;
-use crate::app::section::{primary_section, secondary_section};
+use crate::app::parse_interview::ImportOptions;
+use crate::app::project::ProjectRef;
+use crate::app::project_merge::ProjectMerge;
+use crate::app::reliability::Reliability;
+use crate::app::section::{format_timestamp, primary_section, secondary_section};
 
+mod codebook_import;
+mod consensus;
 mod export;
 mod file_upload;
 mod interview;
+mod notifications;
 mod parse_interview;
+mod project;
+mod project_merge;
+mod reliability;
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct QualityQualitativeCoding {
     settings: Settings,
-    /// the interview itself
-    interview: Option<InterviewSwiper>,
+    /// the interviews being coded, each remembering where the coder is in it
+    #[serde(alias = "interview", deserialize_with = "deserialize_interviews")]
+    interviews: Vec<InterviewSwiper>,
+    /// the position in `interviews` of the interview being coded
+    current_interview: usize,
     /// the codes to choose from
+    #[serde(deserialize_with = "deserialize_codes")]
     codes: Vec<Code>,
+    /// the id the next new code gets, see [`CodeId::next`]
+    next_code_id: CodeId,
     /// a code the user has not added yet,
     code_builder: Code,
     /// receive files asynchronously
     #[serde(skip)]
-    interview_channel: (Sender<Vec<u8>>, Receiver<Vec<u8>>),
+    interview_channel: (Sender<UploadedFile>, Receiver<UploadedFile>),
     #[serde(skip)]
-    codes_channel: (Sender<Vec<u8>>, Receiver<Vec<u8>>),
+    codes_channel: (Sender<UploadedFile>, Receiver<UploadedFile>),
+    #[serde(skip)]
+    project_channel: (Sender<UploadedFile>, Receiver<UploadedFile>),
+    /// where the project was last saved to or opened from
+    project_path: Option<PathBuf>,
     settings_open: bool,
+    save_project_open: bool,
     export_codes_open: bool,
     export_interview_open: bool,
+    export_memos_open: bool,
+    import_options_open: bool,
+    memos_open: bool,
+    reliability_open: bool,
+    /// the codings being compared for inter-rater reliability
+    #[serde(skip)]
+    reliability: Reliability,
+    /// the codings being reconciled into the coding of the project, if any
+    #[serde(skip)]
+    comparison: Option<Comparison>,
+    merge_open: bool,
+    /// the project files of several coders being merged
+    #[serde(skip)]
+    project_merge: ProjectMerge,
+    /// whose codes are shown, `None` for every coder's
+    coder_filter: Option<String>,
+    /// whether the codes of other coders are hidden from the coder in settings until they finish
+    blind: bool,
+    /// how to divide imported interviews into sections
+    import_options: ImportOptions,
+    /// how the last imported codebook was combined with the codebook
+    codebook_import_mode: CodebookImportMode,
+    /// a codebook that has been uploaded but not imported yet
+    #[serde(skip)]
+    pending_codebook: Option<PendingCodebook>,
+    export_interview_options: InterviewExportOptions,
+    #[serde(skip)]
+    export_codes_report: ExportReport,
+    #[serde(skip)]
+    export_interview_report: ExportReport,
+    #[serde(skip)]
+    export_memos_report: ExportReport,
+    #[serde(skip)]
+    notifications: Notifications,
     speaker_builder: String,
+    /// the section text being corrected, if any
+    #[serde(skip)]
+    correction: Option<Correction>,
 }
 
 impl QualityQualitativeCoding {
     pub(crate) fn handle_keyboard_shortcuts(&mut self, ctx: &Context) {
+        let text_id = self
+            .interviews
+            .get(self.current_interview)
+            .map(|interview| section::primary_text_id(self.current_interview, interview.index));
+        if Self::typing(ctx, text_id) {
+            return;
+        }
         let shortcut_map = &self.settings.shortcut_map;
-        Self::handle_next(&mut self.interview, ctx, shortcut_map);
-        Self::handle_prev(&mut self.interview, ctx, shortcut_map);
+        let comparison = self.comparison.as_ref();
+        let interview = self.interviews.get_mut(self.current_interview);
+        Self::handle_next(interview, ctx, shortcut_map, comparison);
+        let interview = self.interviews.get_mut(self.current_interview);
+        Self::handle_prev(interview, ctx, shortcut_map, comparison);
+    }
+
+    /// Moves to the next or previous section or, when comparing codings, to the next or previous
+    /// section the coders disagree on.
+    fn step(interview: &mut InterviewSwiper, comparison: Option<&Comparison>, forward: bool) {
+        match comparison {
+            None if forward => interview.try_next(),
+            None => interview.try_prev(),
+            Some(comparison) => {
+                let name = interview.interview.name.clone();
+                let differs = |index| comparison.differs(&name, index);
+                if forward {
+                    interview.try_next_where(differs)
+                } else {
+                    interview.try_prev_where(differs)
+                }
+            }
+        };
     }
 
     fn handle_prev(
-        interview: &mut Option<InterviewSwiper>,
+        interview: Option<&mut InterviewSwiper>,
         ctx: &Context,
         shortcut_map: &BTreeMap<Action, Key>,
+        comparison: Option<&Comparison>,
     ) {
         if ctx.input().key_pressed(
             shortcut_map
@@ -58,15 +152,16 @@ impl QualityQualitativeCoding {
                 .unwrap_or_else(|| Action::Prev.default_key()),
         ) {
             if let Some(interview) = interview {
-                interview.try_prev();
+                Self::step(interview, comparison, false);
             }
         }
     }
 
     fn handle_next(
-        interview: &mut Option<InterviewSwiper>,
+        interview: Option<&mut InterviewSwiper>,
         ctx: &Context,
         shortcut_map: &BTreeMap<Action, Key>,
+        comparison: Option<&Comparison>,
     ) {
         if ctx.input().key_pressed(
             shortcut_map
@@ -75,8 +170,324 @@ impl QualityQualitativeCoding {
                 .unwrap_or_else(|| Action::Next.default_key()),
         ) {
             if let Some(interview) = interview {
-                interview.try_next();
+                Self::step(interview, comparison, true);
+            }
+        }
+    }
+
+    /// Whether keys are being typed into a text field rather than meant as shortcuts. Selecting the
+    /// text of the section being coded, `primary_text_id`, focuses it but leaves nothing to type.
+    fn typing(ctx: &Context, primary_text_id: Option<egui::Id>) -> bool {
+        ctx.memory()
+            .focus()
+            .map_or(false, |focus| Some(focus) != primary_text_id)
+    }
+
+    /// Applies or removes a code from the section being coded or, if some of its text is
+    /// selected, from the selection.
+    fn code_checkbox(
+        ui: &mut Ui,
+        section: &mut Section,
+        selection: Option<(usize, usize)>,
+        coder: &str,
+        blind: bool,
+        code @ Code { id, name, .. }: &Code,
+    ) {
+        let hover_text = code.hover_text();
+        if let Some((start, end)) = selection {
+            let span = CodedSpan {
+                start,
+                end,
+                code: *id,
+            };
+            let checked = &mut section.spans.contains(&span);
+            let checkbox = ui.checkbox(checked, name).on_hover_text(&hover_text);
+            if checkbox.changed() {
+                if *checked {
+                    section.spans.insert(span);
+                } else {
+                    section.spans.remove(&span);
+                }
+            }
+        } else if coder.is_empty() {
+            // without a coder profile codes are applied to the section without recording who
+            let checked = &mut section.codes.contains(id);
+            let checkbox = ui.checkbox(checked, name).on_hover_text(&hover_text);
+            if checkbox.changed() {
+                if *checked {
+                    section.codes.insert(*id);
+                } else {
+                    section.codes.remove(id);
+                    section
+                        .applications
+                        .retain(|application| application.code != *id);
+                }
+            }
+        } else if Self::unattributed(section, *id, blind) {
+            // codes applied without a coder profile belong to no one, so no coder can take them back
+            ui.add_enabled(false, egui::Checkbox::new(&mut true, name))
+                .on_disabled_hover_text(format!(
+                    "{}\n\napplied without a coder profile, clear your name in the settings to \
+                    remove it",
+                    hover_text
+                ));
+        } else {
+            let checked = &mut section.coded_by(*id, Some(coder));
+            let checkbox = ui.checkbox(checked, name).on_hover_text(&hover_text);
+            if checkbox.changed() {
+                if *checked {
+                    section.apply(*id, coder, now());
+                } else {
+                    section.unapply(*id, coder);
+                }
+            }
+        }
+    }
+
+    /// whether the code was applied to the section without a coder profile and is shown as such.
+    /// While coding blind it is hidden like the codes of every other coder.
+    fn unattributed(section: &Section, id: CodeId, blind: bool) -> bool {
+        !blind && section.codes.contains(&id) && section.coders_of(id).is_empty()
+    }
+
+    /// the section as it is shown to the coder, with only their own codes while coding blind
+    fn visible<'a>(section: &'a Section, blind_coder: Option<&str>) -> Cow<'a, Section> {
+        match blind_coder {
+            Some(coder) => Cow::Owned(section.as_coded_by(coder)),
+            None => Cow::Borrowed(section),
+        }
+    }
+
+    /// the interview as it is exported, with only the coder's own codes while coding blind
+    fn visible_interview<'a>(
+        interview: &'a Interview,
+        blind_coder: Option<&str>,
+    ) -> Cow<'a, Interview> {
+        match blind_coder {
+            Some(coder) => Cow::Owned(interview.as_coded_by(coder)),
+            None => Cow::Borrowed(interview),
+        }
+    }
+
+    /// everyone who applied a code in `sections`, along with `coder` if they have a profile
+    fn coders(sections: &[Section], coder: &str) -> Vec<String> {
+        let mut coders = sections
+            .iter()
+            .flat_map(|section| &section.applications)
+            .map(|application| application.coder.as_str())
+            .chain((!coder.is_empty()).then(|| coder))
+            .collect::<Vec<_>>();
+        coders.sort_unstable();
+        coders.dedup();
+        coders.into_iter().map(str::to_string).collect()
+    }
+
+    /// Picks whose codes to show, one coder's or with `None` all coders'. Only your own coding
+    /// can be changed.
+    fn coder_filter_ui(ui: &mut Ui, filter: &mut Option<String>, coder: &str, coders: Vec<String>) {
+        ui.horizontal(|ui| {
+            ui.label("showing codes of");
+            egui::ComboBox::from_id_source("coder filter")
+                .selected_text(filter.as_deref().unwrap_or("all coders"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(filter, None, "all coders");
+                    for name in coders {
+                        ui.selectable_value(filter, Some(name.clone()), name);
+                    }
+                });
+            if coder.is_empty() {
+                ui.weak("set your name in settings to record who applied each code");
+            }
+        });
+    }
+
+    /// marks a code that coders other than `coder` applied to the whole section
+    fn other_coders_ui(ui: &mut Ui, section: &Section, coder: &str, code: &Code) {
+        let others = section
+            .coders_of(code.id)
+            .into_iter()
+            .filter(|other| *other != coder)
+            .collect::<Vec<_>>();
+        if !others.is_empty() {
+            ui.weak(format!("+{}", others.len()))
+                .on_hover_text(format!("also coded by {}", others.join(", ")));
+        }
+    }
+
+    /// the code as applied by `shown`, who is not the coder using the app, which can't be changed
+    fn others_code_ui(ui: &mut Ui, section: &Section, shown: &str, code: &Code) {
+        let checked = &mut section.coded_by(code.id, Some(shown));
+        ui.add_enabled(false, egui::Checkbox::new(checked, &code.name))
+            .on_disabled_hover_text(format!("coded by {}", shown));
+    }
+
+    /// the codes under `parent` as a tree that can be collapsed, each with the controls to edit it
+    fn code_tree_ui(
+        ui: &mut Ui,
+        codes: &mut [Code],
+        parent: Option<CodeId>,
+        removed: &mut Vec<CodeId>,
+    ) {
+        for id in Code::children_of(codes, parent) {
+            let name = Code::name_of(codes, id).unwrap_or_default().to_string();
+            egui::CollapsingHeader::new(name)
+                .id_source(id)
+                .default_open(true)
+                .show(ui, |ui| {
+                    let parents = Self::parent_choices(codes, Some(id));
+                    if let Some(code) = codes.iter_mut().find(|code| code.id == id) {
+                        ui.group(|ui| {
+                            ui.text_edit_singleline(&mut code.name);
+                            ui.text_edit_singleline(&mut code.description);
+                            Self::parent_picker(ui, id, &mut code.parent, &parents);
+                            let color = code.color();
+                            Self::color_picker(ui, &mut code.color, color);
+                            ui.collapsing("codebook entry", |ui| {
+                                Self::codebook_entry_ui(ui, code);
+                            });
+                            ui.collapsing("memo", |ui| {
+                                ui.add(Self::memo_editor(&mut code.memo));
+                            });
+                            if ui.button("remove").clicked() {
+                                removed.push(id)
+                            }
+                        });
+                    }
+                    Self::code_tree_ui(ui, codes, Some(id), removed);
+                });
+        }
+    }
+
+    /// the fields of a code's entry in the codebook beyond its name and short description
+    fn codebook_entry_ui(ui: &mut Ui, code: &mut Code) {
+        for (label, field) in [
+            ("definition", &mut code.definition),
+            ("when to use", &mut code.inclusion),
+            ("when not to use", &mut code.exclusion),
+            ("example", &mut code.example),
+        ] {
+            ui.label(label);
+            ui.add(
+                egui::TextEdit::multiline(field)
+                    .desired_rows(2)
+                    .desired_width(f32::INFINITY),
+            );
+        }
+    }
+
+    /// the memos on the interview being coded, its current section and the codes applied to it, of
+    /// which a blind coder sees only their own
+    fn memos_ui(
+        ui: &mut Ui,
+        codes: &mut [Code],
+        interview: Option<&mut InterviewSwiper>,
+        blind_coder: Option<&str>,
+    ) {
+        let interview = match interview {
+            Some(interview) => interview,
+            None => {
+                ui.label("upload an interview to write memos on it");
+                return;
             }
+        };
+        ui.heading("interview");
+        ui.add(Self::memo_editor(&mut interview.interview.memo));
+        ui.heading(format!("section {}", interview.index + 1));
+        let section = interview.current_mut();
+        ui.add(Self::memo_editor(&mut section.memo));
+        let section = Self::visible(section, blind_coder);
+        let applied = codes
+            .iter_mut()
+            .filter(|code| section.has_code(code.id))
+            .collect::<Vec<_>>();
+        if !applied.is_empty() {
+            ui.heading("codes of this section");
+        }
+        for code in applied {
+            ui.label(&code.name);
+            ui.add(Self::memo_editor(&mut code.memo));
+        }
+    }
+
+    fn memo_editor(memo: &mut String) -> egui::TextEdit<'_> {
+        egui::TextEdit::multiline(memo)
+            .hint_text("write a memo")
+            .desired_rows(3)
+            .desired_width(f32::INFINITY)
+    }
+
+    /// picks the color of a code, showing `default` until one is picked
+    fn color_picker(ui: &mut Ui, color: &mut Option<CodeColor>, default: CodeColor) {
+        ui.horizontal(|ui| {
+            ui.label("color");
+            let CodeColor(mut rgb) = color.unwrap_or(default);
+            if egui::color_picker::color_edit_button_srgb(ui, &mut rgb).changed() {
+                *color = Some(CodeColor(rgb));
+            }
+        });
+    }
+
+    /// The codes that can be the parent of the code with the given id, which is every code but the
+    /// code itself and those that are a part of it.
+    fn parent_choices(codes: &[Code], id: Option<CodeId>) -> Vec<(CodeId, String)> {
+        codes
+            .iter()
+            .filter(|code| id.map_or(true, |id| Code::ancestry(codes, code.id).all(|a| a != id)))
+            .map(|code| (code.id, code.name.clone()))
+            .collect()
+    }
+
+    fn parent_picker(
+        ui: &mut Ui,
+        id_source: impl Hash,
+        parent: &mut Option<CodeId>,
+        choices: &[(CodeId, String)],
+    ) {
+        let selected = parent
+            .and_then(|parent| choices.iter().find(|(id, _)| *id == parent))
+            .map_or("none", |(_, name)| name.as_str());
+        ui.horizontal(|ui| {
+            ui.label("part of");
+            egui::ComboBox::from_id_source(("parent", id_source))
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(parent, None, "none");
+                    for (id, name) in choices {
+                        ui.selectable_value(parent, Some(*id), name);
+                    }
+                });
+        });
+    }
+
+    /// which of the coders being compared applied a code, by their number
+    fn coders_ui(ui: &mut Ui, comparison: &Comparison, applied: [bool; 2]) {
+        for ((number, name), applied) in ["1", "2"].into_iter().zip(comparison.names()).zip(applied)
+        {
+            let number = egui::RichText::new(number).small();
+            if applied {
+                ui.label(number.strong())
+                    .on_hover_text(format!("coded by {}", name));
+            } else {
+                ui.label(number.weak())
+                    .on_hover_text(format!("not coded by {}", name));
+            }
+        }
+    }
+
+    /// the codes applied to parts of the section being coded, which can be removed from here
+    fn coded_spans_ui(ui: &mut Ui, codes: &[Code], section: &mut Section) {
+        let mut removed = None;
+        for span in &section.spans {
+            ui.horizontal(|ui| {
+                ui.label(Code::name_of(codes, span.code).unwrap_or_default());
+                ui.weak(format!("\"{}\"", section.excerpt(span)));
+                if ui.small_button("remove").clicked() {
+                    removed = Some(*span);
+                }
+            });
+        }
+        if let Some(span) = removed {
+            section.spans.remove(&span);
         }
     }
 
@@ -86,6 +497,141 @@ impl QualityQualitativeCoding {
         hasher.finish()
     }
 
+    /// the controls for correcting the text of the section being coded along with splitting and
+    /// merging it. Splits and merges are returned rather than applied as the neighbouring sections
+    /// are borrowed for display.
+    fn correction_ui(
+        ui: &mut Ui,
+        correction: &mut Option<Correction>,
+        current_interview: usize,
+        index: usize,
+        section_count: usize,
+        section: &mut Section,
+        notifications: &mut Notifications,
+    ) -> Option<Restructure> {
+        match correction {
+            Some(Correction {
+                interview,
+                section: section_index,
+                replacing,
+                text,
+            }) if *interview == current_interview
+                && *section_index == index
+                && *replacing == section.text =>
+            {
+                let output = egui::TextEdit::multiline(text)
+                    .desired_width(f32::INFINITY)
+                    .show(ui);
+                let cursor = output
+                    .state
+                    .ccursor_range()
+                    .map(|range| range.primary.index);
+                let (save, split, cancel) = ui
+                    .horizontal(|ui| {
+                        (
+                            ui.button("save").clicked(),
+                            ui.add_enabled(cursor.is_some(), egui::Button::new("split at cursor"))
+                                .on_hover_text(
+                                    "save and move the text after the cursor into a new section",
+                                )
+                                .clicked(),
+                            ui.button("cancel").clicked(),
+                        )
+                    })
+                    .inner;
+                if save || split {
+                    let dropped = section.correct(std::mem::take(text));
+                    info!(current_interview, index, dropped, "corrected section text");
+                    Self::report_dropped_spans(notifications, dropped);
+                }
+                if save || split || cancel {
+                    *correction = None;
+                }
+                cursor.filter(|_| split).map(Restructure::SplitAt)
+            }
+            _ => {
+                ui.horizontal(|ui| {
+                    let mut restructure = None;
+                    if ui
+                        .add_enabled(index > 0, egui::Button::new("merge with previous"))
+                        .clicked()
+                    {
+                        restructure = Some(Restructure::MergePrevious);
+                    }
+                    if ui
+                        .add_enabled(
+                            index + 1 < section_count,
+                            egui::Button::new("merge with next"),
+                        )
+                        .clicked()
+                    {
+                        restructure = Some(Restructure::MergeNext);
+                    }
+                    if ui
+                        .button("edit text")
+                        .on_hover_text("correct the transcription of this section")
+                        .clicked()
+                    {
+                        *correction = Some(Correction {
+                            interview: current_interview,
+                            section: index,
+                            replacing: section.text.clone(),
+                            text: section.text.clone(),
+                        });
+                    }
+                    if let Some(original) = &section.original_text {
+                        if ui
+                            .button("restore transcript")
+                            .on_hover_text(original)
+                            .clicked()
+                        {
+                            let dropped = section.restore_original();
+                            Self::report_dropped_spans(notifications, dropped);
+                        }
+                    }
+                    restructure
+                })
+                .inner
+            }
+        }
+    }
+
+    fn report_dropped_spans(notifications: &mut Notifications, dropped: usize) {
+        if dropped > 0 {
+            notifications.warning(
+                "changed the text of the section",
+                format!(
+                    "{} coded excerpt(s) no longer in the text were removed",
+                    dropped
+                ),
+            );
+        }
+    }
+
+    /// Splits or merges sections. Both a pending correction and a comparison refer to sections by
+    /// position, which the restructure shifts, so neither is kept.
+    fn restructure(
+        interview: &mut InterviewSwiper,
+        restructure: Restructure,
+        correction: &mut Option<Correction>,
+        comparison: &mut Option<Comparison>,
+        notifications: &mut Notifications,
+    ) {
+        info!(
+            ?restructure,
+            index = interview.index,
+            "restructuring sections"
+        );
+        interview.restructure(restructure);
+        *correction = None;
+        if comparison.take().is_some() {
+            notifications.warning(
+                "restructured the sections",
+                "stopped comparing codings as they no longer line up with the sections",
+            );
+        }
+    }
+
     fn set_key_for_action(settings: &mut Settings, ui: &mut Ui, action: &Action) {
         ui.group(|ui| {
             ui.label(format!(
@@ -128,12 +674,17 @@ impl Action {
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(default)]
 struct Settings {
     code_columns: usize,
     shortcut_map: BTreeMap<Action, Key>,
     setting_key: Option<Action>,
     context_before: usize,
     context_after: usize,
+    /// words transcribed with a confidence below this are highlighted
+    confidence_threshold: f64,
+    /// the name codes are applied under, so several coders can code the same transcript
+    coder: String,
 }
 
 impl Default for Settings {
@@ -144,6 +695,8 @@ impl Default for Settings {
             setting_key: None,
             context_before: 1,
             context_after: 1,
+            confidence_threshold: 0.8,
+            coder: String::new(),
         }
     }
 }
@@ -152,48 +705,920 @@ impl Default for QualityQualitativeCoding {
     fn default() -> Self {
         Self {
             settings: Default::default(),
-            interview: None,
+            interviews: Vec::default(),
+            current_interview: 0,
             codes: Vec::default(),
-            code_builder: Code {
-                name: "".to_string(),
-                description: "".to_string(),
-            },
+            next_code_id: CodeId::default(),
+            code_builder: Code::default(),
             interview_channel: channel(),
             codes_channel: channel(),
+            project_channel: channel(),
+            project_path: None,
             settings_open: false,
+            save_project_open: false,
             export_codes_open: false,
             export_interview_open: false,
+            export_memos_open: false,
+            import_options_open: false,
+            memos_open: false,
+            reliability_open: false,
+            reliability: Reliability::default(),
+            merge_open: false,
+            project_merge: ProjectMerge::default(),
+            comparison: None,
+            coder_filter: None,
+            blind: false,
+            import_options: ImportOptions::default(),
+            codebook_import_mode: CodebookImportMode::default(),
+            pending_codebook: None,
+            export_interview_options: InterviewExportOptions::default(),
+            export_codes_report: ExportReport::default(),
+            export_interview_report: ExportReport::default(),
+            export_memos_report: ExportReport::default(),
+            notifications: Notifications::default(),
             speaker_builder: "".to_string(),
+            correction: None,
         }
     }
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+/// A stable identifier for a [Code]. Unlike a position in the codebook it does not change when
+/// codes are removed, reordered or re-imported.
+#[derive(
+    serde::Deserialize,
+    serde::Serialize,
+    Ord,
+    PartialOrd,
+    Eq,
+    PartialEq,
+    Hash,
+    Debug,
+    Default,
+    Copy,
+    Clone,
+)]
+#[serde(transparent)]
+pub struct CodeId(u64);
+
+impl CodeId {
+    /// An id for a new code: one more than the largest id in `codes` or, if that is smaller,
+    /// `issued`, the id the project would give its next code. Removed codes are no longer in
+    /// `codes` but their ids are below `issued`, so they are never given out again.
+    fn next(codes: &[Code], issued: CodeId) -> CodeId {
+        codes
+            .iter()
+            .map(|code| CodeId(code.id.0 + 1))
+            .max()
+            .unwrap_or_default()
+            .max(issued)
+    }
+
+    /// One more than the largest id any of `sections` is coded with. State saved before codes had
+    /// ids can have sections coded with the position of a removed code, which a new code must not
+    /// be given.
+    fn after_sections<'a>(sections: impl IntoIterator<Item = &'a Section>) -> CodeId {
+        sections
+            .into_iter()
+            .flat_map(|section| {
+                let spans = section.spans.iter().map(|span| span.code);
+                let applications = section.applications.iter().map(|a| a.code);
+                section
+                    .codes
+                    .iter()
+                    .copied()
+                    .chain(spans)
+                    .chain(applications)
+            })
+            .map(|CodeId(id)| CodeId(id + 1))
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+#[derive(
+    serde::Deserialize,
+    serde::Serialize,
+    Clone,
+    Default,
+    Ord,
+    PartialOrd,
+    Eq,
+    PartialEq,
+    Hash,
+    Debug,
+)]
 pub struct Code {
+    #[serde(default)]
+    id: CodeId,
     name: String,
     description: String,
+    /// the theme this code is a part of
+    #[serde(default)]
+    parent: Option<CodeId>,
+    /// the color coded text is highlighted with, picked from [CodeColor::PALETTE] if not chosen
+    #[serde(default)]
+    color: Option<CodeColor>,
+    /// the coder's analytic notes on the code, kept apart from its short description
+    #[serde(default)]
+    memo: String,
+    /// the full definition of the code
+    #[serde(default)]
+    definition: String,
+    /// when to apply the code
+    #[serde(default)]
+    inclusion: String,
+    /// when not to apply the code, such as when a similar code fits better
+    #[serde(default)]
+    exclusion: String,
+    /// a quote that exemplifies the code
+    #[serde(default)]
+    example: String,
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Default, Debug)]
+/// The color of a code, written as `#rrggbb` in saved projects and codebook csvs.
+#[derive(Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Copy, Clone)]
+pub struct CodeColor([u8; 3]);
+
+impl CodeColor {
+    /// colors that are easy to tell apart on both light and dark backgrounds
+    const PALETTE: [CodeColor; 10] = [
+        CodeColor([0x4e, 0x79, 0xa7]),
+        CodeColor([0xf2, 0x8e, 0x2b]),
+        CodeColor([0xe1, 0x57, 0x59]),
+        CodeColor([0x76, 0xb7, 0xb2]),
+        CodeColor([0x59, 0xa1, 0x4f]),
+        CodeColor([0xed, 0xc9, 0x48]),
+        CodeColor([0xb0, 0x7a, 0xa1]),
+        CodeColor([0xff, 0x9d, 0xa7]),
+        CodeColor([0x9c, 0x75, 0x5f]),
+        CodeColor([0xba, 0xb0, 0xac]),
+    ];
+
+    /// parses `#rrggbb`, with or without the `#`
+    fn parse(hex: &str) -> Option<CodeColor> {
+        let hex = hex.trim();
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |at: usize| u8::from_str_radix(&hex[at..at + 2], 16).ok();
+        Some(CodeColor([channel(0)?, channel(2)?, channel(4)?]))
+    }
+
+    fn to_hex(self) -> String {
+        let CodeColor([r, g, b]) = self;
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+}
+
+impl From<CodeColor> for egui::Color32 {
+    fn from(CodeColor([r, g, b]): CodeColor) -> Self {
+        egui::Color32::from_rgb(r, g, b)
+    }
+}
+
+impl serde::Serialize for CodeColor {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for CodeColor {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = <String as serde::Deserialize>::deserialize(deserializer)?;
+        CodeColor::parse(&hex).ok_or_else(|| {
+            serde::de::Error::custom(format!("expected a color like #4e79a7, got \"{}\"", hex))
+        })
+    }
+}
+
+/// A code as it appears in persisted state or an imported codebook, where the id may be missing.
+#[derive(serde::Deserialize, Debug)]
+struct MaybeIdentifiedCode {
+    #[serde(default)]
+    id: Option<CodeId>,
+    name: String,
+    description: String,
+    #[serde(default)]
+    parent: Option<CodeId>,
+    #[serde(default)]
+    color: Option<CodeColor>,
+    #[serde(default)]
+    memo: String,
+    #[serde(default)]
+    definition: String,
+    #[serde(default)]
+    inclusion: String,
+    #[serde(default)]
+    exclusion: String,
+    #[serde(default)]
+    example: String,
+}
+
+/// A row of a codebook csv. Parents are referred to by name so that codebooks can be written by
+/// hand.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct CsvCode {
+    #[serde(default)]
+    id: Option<CodeId>,
+    name: String,
+    description: String,
+    #[serde(default)]
+    parent: Option<String>,
+    #[serde(default)]
+    color: Option<CodeColor>,
+    #[serde(default)]
+    definition: String,
+    #[serde(default)]
+    inclusion: String,
+    #[serde(default)]
+    exclusion: String,
+    #[serde(default)]
+    example: String,
+}
+
+impl CsvCode {
+    fn from_code(codes: &[Code], code: &Code) -> CsvCode {
+        CsvCode {
+            id: Some(code.id),
+            name: code.name.clone(),
+            description: code.description.clone(),
+            parent: Code::parent_of(codes, code.id)
+                .and_then(|parent| Code::name_of(codes, parent))
+                .map(String::from),
+            color: Some(code.color()),
+            definition: code.definition.clone(),
+            inclusion: code.inclusion.clone(),
+            exclusion: code.exclusion.clone(),
+            example: code.example.clone(),
+        }
+    }
+}
+
+impl Code {
+    /// the description of the code followed by the rest of its codebook entry, for hovering
+    fn hover_text(&self) -> String {
+        [
+            ("", &self.description),
+            ("definition: ", &self.definition),
+            ("use when: ", &self.inclusion),
+            ("do not use when: ", &self.exclusion),
+            ("e.g. ", &self.example),
+        ]
+        .into_iter()
+        .filter(|(_, field)| !field.is_empty())
+        .map(|(label, field)| format!("{}{}", label, field))
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+
+    /// the color chosen for the code or, if none was, one of the palette that stays the same
+    fn color(&self) -> CodeColor {
+        self.color.unwrap_or_else(|| {
+            CodeColor::PALETTE[(self.id.0 % CodeColor::PALETTE.len() as u64) as usize]
+        })
+    }
+
+    /// the color of the code with the given id, or grey if it is no longer in the codebook
+    fn color_of(codes: &[Code], id: CodeId) -> egui::Color32 {
+        codes
+            .iter()
+            .find(|code| code.id == id)
+            .map_or(egui::Color32::GRAY, |code| code.color().into())
+    }
+
+    /// the name of the code with the given id, if it is still in the codebook
+    fn name_of(codes: &[Code], id: CodeId) -> Option<&str> {
+        codes
+            .iter()
+            .find(|code| code.id == id)
+            .map(|code| code.name.as_str())
+    }
+
+    /// the parent of the code with the given id, if it has one that is still in the codebook
+    fn parent_of(codes: &[Code], id: CodeId) -> Option<CodeId> {
+        codes
+            .iter()
+            .find(|code| code.id == id)?
+            .parent
+            .filter(|parent| codes.iter().any(|code| code.id == *parent))
+    }
+
+    /// the code with the given id followed by its parent, its parent's parent and so on
+    fn ancestry(codes: &[Code], id: CodeId) -> impl Iterator<Item = CodeId> + '_ {
+        // the take guards against a cycle, which would be a bug but should not hang the app
+        std::iter::successors(Some(id), |id| Code::parent_of(codes, *id)).take(codes.len() + 1)
+    }
+
+    /// the top-level theme the code with the given id is a part of, which may be the code itself
+    fn root_of(codes: &[Code], id: CodeId) -> CodeId {
+        Code::ancestry(codes, id).last().unwrap_or(id)
+    }
+
+    /// the codes directly under `parent` or, if it is `None`, the top-level codes
+    fn children_of(codes: &[Code], parent: Option<CodeId>) -> Vec<CodeId> {
+        codes
+            .iter()
+            .filter(|code| Code::parent_of(codes, code.id) == parent)
+            .map(|code| code.id)
+            .collect()
+    }
+
+    /// Makes each code at the given position a part of the code with the given name. Unknown
+    /// names, and parents that would make a code a part of itself, leave the code at the top level.
+    fn set_parents_by_name(codes: &mut [Code], parents: Vec<(usize, String)>) {
+        for (index, parent) in parents {
+            let id = codes[index].id;
+            let parent = codes
+                .iter()
+                .find(|code| code.name == parent && code.id != id)
+                .map(|code| code.id);
+            codes[index].parent = parent;
+            if parent.map_or(false, |parent| {
+                Code::ancestry(codes, parent).any(|a| a == id)
+            }) {
+                codes[index].parent = None;
+            }
+        }
+    }
+}
+
+impl MaybeIdentifiedCode {
+    fn identify_or(self, fallback: CodeId) -> Code {
+        Code {
+            id: self.id.unwrap_or(fallback),
+            name: self.name,
+            description: self.description,
+            parent: self.parent,
+            color: self.color,
+            memo: self.memo,
+            definition: self.definition,
+            inclusion: self.inclusion,
+            exclusion: self.exclusion,
+            example: self.example,
+        }
+    }
+}
+
+/// Deserializes the codebook, giving codes from state saved before codes had ids their position as
+/// an id. Sections used to reference codes by position so this keeps their coding intact.
+fn deserialize_codes<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Code>, D::Error> {
+    let codes = <Vec<MaybeIdentifiedCode> as serde::Deserialize>::deserialize(deserializer)?;
+    Ok(codes
+        .into_iter()
+        .enumerate()
+        .map(|(idx, code)| code.identify_or(CodeId(idx as u64)))
+        .collect())
+}
+
+/// Deserializes the interviews, accepting the single optional interview of state saved before
+/// projects could hold more than one.
+fn deserialize_interviews<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<InterviewSwiper>, D::Error> {
+    use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+    use serde::de::{MapAccess, SeqAccess, Visitor};
+    use serde::Deserialize;
+
+    struct InterviewsVisitor;
+
+    impl<'de> Visitor<'de> for InterviewsVisitor {
+        type Value = Vec<InterviewSwiper>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a list of interviews or a single optional interview")
+        }
+
+        fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+            Ok(Vec::new())
+        }
+
+        fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+            Ok(Vec::new())
+        }
+
+        fn visit_some<D: serde::Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_any(self)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+            Vec::deserialize(SeqAccessDeserializer::new(seq))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+            InterviewSwiper::deserialize(MapAccessDeserializer::new(map)).map(|one| vec![one])
+        }
+    }
+
+    deserializer.deserialize_any(InterviewsVisitor)
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Default, Clone, Debug)]
 pub struct Interview {
+    /// the name of the file the interview was imported from, without its extension
+    #[serde(default)]
+    name: String,
     /// speaker_id and names
     speakers: BTreeMap<u64, String>,
     /// the sections of speach
     sections: Vec<Section>,
+    /// the coder's analytic notes on the interview as a whole
+    #[serde(default)]
+    memo: String,
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Default, Debug)]
+impl Interview {
+    /// the interview as `coder` coded it, see [`Section::as_coded_by`]
+    fn as_coded_by(&self, coder: &str) -> Interview {
+        Interview {
+            name: self.name.clone(),
+            speakers: self.speakers.clone(),
+            sections: self
+                .sections
+                .iter()
+                .map(|section| section.as_coded_by(coder))
+                .collect(),
+            memo: self.memo.clone(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Default, Clone, Debug)]
 pub struct Section {
     speaker_id: u64,
     text: String,
-    /// references the key of a code
-    codes: BTreeSet<usize>,
+    /// references the id of a code
+    codes: BTreeSet<CodeId>,
+    /// when the section starts in the recording, in seconds
+    start: Option<f64>,
+    /// when the section ends in the recording, in seconds
+    end: Option<f64>,
+    /// the words of `text` along with how confident the transcription was in them, if the
+    /// transcript said
+    #[serde(default)]
+    words: Vec<Word>,
+    /// the text as transcribed, if it has since been corrected
+    #[serde(default)]
+    original_text: Option<String>,
+    /// codes applied to parts of the text rather than all of it
+    #[serde(default)]
+    spans: BTreeSet<CodedSpan>,
+    /// the coder's analytic notes on the section
+    #[serde(default)]
+    memo: String,
+    /// how the coders' disagreement on the section was settled, if they have met about it
+    #[serde(default)]
+    review: Option<Review>,
+    /// who applied each of `codes` and when. Codes applied before coders were recorded, or taken
+    /// from a consensus, have no application.
+    #[serde(default)]
+    applications: Vec<CodeApplication>,
+}
+
+/// A code applied to a whole section by a coder.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Eq, PartialEq)]
+pub struct CodeApplication {
+    code: CodeId,
+    /// the name in the profile of the coder who applied the code
+    coder: String,
+    /// when the code was applied, in seconds since the unix epoch
+    at: u64,
+}
+
+/// a time in seconds since the unix epoch as an ISO 8601 date and time in UTC
+fn format_unix_time(seconds: u64) -> String {
+    // the civil from days algorithm, http://howardhinnant.github.io/date_algorithms.html
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    let time = seconds % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// the current time in seconds since the unix epoch
+#[cfg(not(target_arch = "wasm32"))]
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// the current time in seconds since the unix epoch
+#[cfg(target_arch = "wasm32")]
+fn now() -> u64 {
+    (js_sys::Date::now() / 1000.0) as u64
+}
+
+/// A code applied to an excerpt of a section's text.
+#[derive(
+    serde::Deserialize, serde::Serialize, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug,
+)]
+pub struct CodedSpan {
+    /// the position of the first character of the excerpt
+    start: usize,
+    /// the position after the last character of the excerpt
+    end: usize,
+    code: CodeId,
+}
+
+impl CodedSpan {
+    /// The part of the span within the characters `from..to`, moved so that `from` is at `to_start`.
+    fn moved(self, from: usize, to: usize, to_start: usize) -> Option<CodedSpan> {
+        let start = self.start.clamp(from, to);
+        let end = self.end.clamp(from, to);
+        (start < end).then(|| CodedSpan {
+            start: start - from + to_start,
+            end: end - from + to_start,
+            code: self.code,
+        })
+    }
+}
+
+/// the characters `start..end` of `text`
+fn char_slice(text: &str, start: usize, end: usize) -> &str {
+    let byte = |position: usize| {
+        text.char_indices()
+            .nth(position)
+            .map_or(text.len(), |(index, _)| index)
+    };
+    &text[byte(start)..byte(end.max(start))]
+}
+
+/// trims whitespace from `text`, returning the position of the first character left
+fn trim_with_position(text: &str) -> (String, usize) {
+    let leading = text.chars().take_while(|char| char.is_whitespace()).count();
+    (text.trim().to_string(), leading)
+}
+
+impl Section {
+    /// Replaces the text with a correction, remembering the transcript it replaced. Returns how
+    /// many coded spans were dropped as their excerpt was corrected away.
+    fn correct(&mut self, text: String) -> usize {
+        let original = self
+            .original_text
+            .take()
+            .unwrap_or_else(|| self.text.clone());
+        if original != text {
+            self.original_text = Some(original);
+        }
+        self.replace_text(text)
+    }
+
+    /// Undoes any corrections to the text. Returns how many coded spans were dropped.
+    fn restore_original(&mut self) -> usize {
+        match self.original_text.take() {
+            Some(original) => self.replace_text(original),
+            None => 0,
+        }
+    }
+
+    /// the part of the text a span codes
+    fn excerpt(&self, CodedSpan { start, end, .. }: &CodedSpan) -> &str {
+        char_slice(&self.text, *start, *end)
+    }
+
+    /// Changes the text, keeping coded spans on the same words. Only the part between the
+    /// unchanged start and end of the text is edited: spans outside of it shift along, spans that
+    /// touch it move to the nearest copy of their excerpt or shrink to what is left of it. Returns
+    /// how many spans were dropped as none of their excerpt is left.
+    fn replace_text(&mut self, text: String) -> usize {
+        let old = self.text.chars().collect::<Vec<_>>();
+        let new = text.chars().collect::<Vec<_>>();
+        let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        // the edited part is `prefix..old_end` in the old text and `prefix..new_end` in the new
+        let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+        let moved = |at: usize| {
+            if at <= prefix {
+                at
+            } else {
+                (at.max(old_end) - old_end) + new_end
+            }
+        };
+        let spans = std::mem::take(&mut self.spans);
+        let count = spans.len();
+        self.spans = spans
+            .into_iter()
+            .filter_map(|span| {
+                let untouched = span.end <= prefix
+                    || span.start >= old_end
+                    || (span.start <= prefix && span.end >= old_end);
+                let (start, end) = if untouched {
+                    (moved(span.start), moved(span.end))
+                } else {
+                    let excerpt = char_slice(&self.text, span.start, span.end);
+                    text.match_indices(excerpt)
+                        .map(|(index, _)| text[..index].chars().count())
+                        .min_by_key(|start| start.abs_diff(span.start))
+                        .map(|start| (start, start + excerpt.chars().count()))
+                        // what is left of the excerpt either side of the edit
+                        .unwrap_or_else(|| {
+                            if span.start < prefix {
+                                (span.start, prefix)
+                            } else {
+                                (new_end, moved(span.end))
+                            }
+                        })
+                };
+                (start < end).then(|| CodedSpan {
+                    start,
+                    end,
+                    code: span.code,
+                })
+            })
+            .collect();
+        self.text = text;
+        count - self.spans.len()
+    }
+
+    /// a copy with every code replaced by the top-level theme it is a part of
+    fn rolled_up(&self, codes: &[Code]) -> Section {
+        let mut rolled_up = Section {
+            codes: self
+                .codes
+                .iter()
+                .map(|id| Code::root_of(codes, *id))
+                .collect(),
+            spans: self
+                .spans
+                .iter()
+                .map(|span| CodedSpan {
+                    code: Code::root_of(codes, span.code),
+                    ..*span
+                })
+                .collect(),
+            applications: Vec::new(),
+            ..self.clone()
+        };
+        // a coder who applied several codes under a theme applied the theme once
+        for application in &self.applications {
+            let root = Code::root_of(codes, application.code);
+            rolled_up.apply(root, &application.coder, application.at);
+        }
+        rolled_up
+    }
+
+    /// Whether `coder`, or with `None` anyone, coded the whole section with `code`.
+    fn coded_by(&self, code: CodeId, coder: Option<&str>) -> bool {
+        match coder {
+            None => self.codes.contains(&code),
+            Some(coder) => self
+                .applications
+                .iter()
+                .any(|application| application.code == code && application.coder == coder),
+        }
+    }
+
+    /// the coders who applied `code` to the whole section, in the order they applied it
+    fn coders_of(&self, code: CodeId) -> Vec<&str> {
+        let mut coders = Vec::new();
+        for application in self.applications.iter().filter(|a| a.code == code) {
+            if !coders.contains(&application.coder.as_str()) {
+                coders.push(application.coder.as_str());
+            }
+        }
+        coders
+    }
+
+    /// Codes the whole section with `code` on behalf of `coder` at `at`.
+    fn apply(&mut self, code: CodeId, coder: &str, at: u64) {
+        self.codes.insert(code);
+        if !self.coded_by(code, Some(coder)) {
+            self.applications.push(CodeApplication {
+                code,
+                coder: coder.to_string(),
+                at,
+            });
+        }
+    }
+
+    /// Takes back `coder`'s application of `code`. The code stays on the section while other
+    /// coders have applied it.
+    fn unapply(&mut self, code: CodeId, coder: &str) {
+        self.applications
+            .retain(|application| application.code != code || application.coder != coder);
+        if self
+            .applications
+            .iter()
+            .all(|application| application.code != code)
+        {
+            self.codes.remove(&code);
+        }
+    }
+
+    /// The section with only the codes `coder` applied. Coded excerpts are left out as they are
+    /// not recorded per coder.
+    fn as_coded_by(&self, coder: &str) -> Section {
+        let applications = self
+            .applications
+            .iter()
+            .filter(|application| application.coder == coder)
+            .cloned()
+            .collect::<Vec<_>>();
+        Section {
+            codes: applications
+                .iter()
+                .map(|application| application.code)
+                .collect(),
+            spans: BTreeSet::new(),
+            applications,
+            ..self.clone()
+        }
+    }
+
+    /// whether the section or part of it has been coded with `code`
+    fn has_code(&self, code: CodeId) -> bool {
+        self.codes.contains(&code) || self.spans.iter().any(|span| span.code == code)
+    }
+
+    /// keeps the codes, of the whole section and of its spans, for which `keep` is true
+    fn retain_codes(&mut self, mut keep: impl FnMut(&CodeId) -> bool) {
+        self.codes.retain(&mut keep);
+        self.spans.retain(|span| keep(&span.code));
+        self.applications
+            .retain(|application| keep(&application.code));
+    }
+
+    /// Splits the section before the character at `at`, returning the rest as a section with the
+    /// same speaker and codes. Where the split happens in the recording is unknown so the halves
+    /// are left without an end and a start respectively. A corrected section keeps its whole
+    /// transcript, and its memo, in the first half.
+    fn split_off(&mut self, at: usize) -> Section {
+        let at_byte = self
+            .text
+            .char_indices()
+            .nth(at)
+            .map_or(self.text.len(), |(index, _)| index);
+        let at = self.text[..at_byte].chars().count();
+        let (rest, rest_start) = trim_with_position(&self.text.split_off(at_byte));
+        let (first, first_start) = trim_with_position(&self.text);
+        self.text = first;
+        let spans = std::mem::take(&mut self.spans);
+        let first_end = first_start + self.text.chars().count();
+        self.spans = spans
+            .iter()
+            .filter_map(|span| span.moved(first_start, first_end, 0))
+            .collect();
+        let rest_start = at + rest_start;
+        let rest_end = rest_start + rest.chars().count();
+        let rest_spans = spans
+            .iter()
+            .filter_map(|span| span.moved(rest_start, rest_end, 0))
+            .collect();
+        let mut words = std::mem::take(&mut self.words);
+        let first_words = self.text.split_whitespace().count();
+        // only keep the words if the split fell between two of them
+        if first_words + rest.split_whitespace().count() == words.len() {
+            self.words = words.drain(..first_words).collect();
+        } else {
+            words.clear();
+        }
+        Section {
+            speaker_id: self.speaker_id,
+            text: rest,
+            codes: self.codes.clone(),
+            applications: self.applications.clone(),
+            start: None,
+            end: self.end.take(),
+            words,
+            spans: rest_spans,
+            ..Default::default()
+        }
+    }
+
+    /// Appends the section after this one, keeping this section's speaker and the codes and memos of
+    /// both.
+    fn merge(&mut self, next: Section) {
+        if self.original_text.is_some() || next.original_text.is_some() {
+            self.original_text = Some(format!(
+                "{} {}",
+                self.original_text.as_ref().unwrap_or(&self.text),
+                next.original_text.as_ref().unwrap_or(&next.text)
+            ));
+        }
+        let length = self.text.trim_end().chars().count();
+        let next_start = next
+            .text
+            .chars()
+            .take_while(|char| char.is_whitespace())
+            .count();
+        let next_end = next.text.trim_end().chars().count();
+        self.spans = std::mem::take(&mut self.spans)
+            .into_iter()
+            .filter_map(|span| span.moved(0, length, 0))
+            .chain(
+                next.spans
+                    .iter()
+                    .filter_map(|span| span.moved(next_start, next_end, length + 1)),
+            )
+            .collect();
+        self.text = format!("{} {}", self.text.trim_end(), next.text.trim_start());
+        self.codes.extend(next.codes);
+        for application in next.applications {
+            if !self.coded_by(application.code, Some(&application.coder)) {
+                self.applications.push(application);
+            }
+        }
+        self.end = next.end;
+        if self.memo.is_empty() {
+            self.memo = next.memo;
+        } else if !next.memo.is_empty() {
+            self.memo = format!("{}\n\n{}", self.memo, next.memo);
+        }
+        self.review = self.review.max(next.review);
+        if self.words.is_empty() == next.words.is_empty() {
+            self.words.extend(next.words);
+        } else {
+            // highlighting some words but not others would suggest the rest are certain
+            self.words.clear();
+        }
+    }
+}
+
+/// A correction to the text of a section that has not been saved yet.
+#[derive(Debug)]
+struct Correction {
+    /// the position of the interview in the project
+    interview: usize,
+    /// the position of the section in the interview
+    section: usize,
+    /// the text of the section when the correction began. Positions change as interviews are
+    /// opened, removed, split and merged, so the correction is only for the section at its
+    /// position while that still has this text.
+    replacing: String,
+    text: String,
+}
+
+impl Correction {
+    /// whether the section the correction was begun on is still at its position
+    fn is_of(&self, interviews: &[InterviewSwiper]) -> bool {
+        interviews
+            .get(self.interview)
+            .and_then(|swiper| swiper.interview.sections.get(self.section))
+            .map_or(false, |section| section.text == self.replacing)
+    }
+}
+
+/// A transcribed word.
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct Word {
+    text: String,
+    /// how likely the transcription is to be right, from 0 to 1
+    confidence: Option<f64>,
+    /// false if a person has edited the word since it was transcribed
+    pristine: Option<bool>,
+}
+
+impl Word {
+    /// whether the word is likely a transcription error worth a second look. Words a person has
+    /// edited are trusted regardless of their confidence.
+    fn is_uncertain(&self, threshold: f64) -> bool {
+        self.pristine != Some(false)
+            && self
+                .confidence
+                .map_or(false, |confidence| confidence < threshold)
+    }
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 pub struct CsvSerializableSection {
+    interview: String,
     speaker: String,
+    start: Option<String>,
+    end: Option<String>,
     text: String,
+    /// the text as transcribed, only exported when asked for
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    original_text: Option<String>,
     code0: Option<String>,
     code1: Option<String>,
     code2: Option<String>,
@@ -203,62 +1628,269 @@ pub struct CsvSerializableSection {
     code6: Option<String>,
     code7: Option<String>,
     code8: Option<String>,
+    /// the codes applied to parts of the text along with the excerpts they code
+    excerpts: Option<String>,
+    /// the memo on the section, only exported when asked for
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    memo: Option<String>,
+    /// the memo on the interview, exported along with the section memos
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    interview_memo: Option<String>,
+    /// who applied each code to the whole section, only exported when asked for
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    coded_by: Option<String>,
 }
 
 impl CsvSerializableSection {
+    /// how many codes of a section fit in `code0` to `code8`
+    const CODE_COLUMNS: usize = 9;
+
     fn from_section(
-        speakers: &BTreeMap<u64, String>,
-        codes: &[Code],
-        Section {
+        Interview {
+            name,
+            speakers,
+            memo: interview_memo,
+            ..
+        }: &Interview,
+        all_codes: &[Code],
+        section @ Section {
             speaker_id,
             codes: section_codes,
             text,
+            start,
+            end,
+            original_text,
+            spans,
+            memo,
+            ..
         }: &Section,
+        Columns {
+            include_original,
+            include_memos,
+            include_coders,
+        }: Columns,
     ) -> CsvSerializableSection {
+        if section_codes.len() > Self::CODE_COLUMNS {
+            warn!(
+                count = section_codes.len(),
+                "section has more codes than there are columns, use the long format to export them all"
+            );
+        }
         let mut codes = section_codes
             .iter()
-            .map(|id| codes.get(*id).unwrap())
-            .map(|Code { name, .. }| name);
+            .filter_map(|id| Code::name_of(all_codes, *id))
+            .map(String::from);
         CsvSerializableSection {
-            speaker: speakers[speaker_id].clone(),
+            interview: name.clone(),
+            speaker: speaker_name(speakers, *speaker_id),
+            start: start.map(format_timestamp),
+            end: end.map(format_timestamp),
             text: text.clone(),
-            code0: codes.next().cloned(),
-            code1: codes.next().cloned(),
-            code2: codes.next().cloned(),
-            code3: codes.next().cloned(),
-            code4: codes.next().cloned(),
-            code5: codes.next().cloned(),
-            code6: codes.next().cloned(),
-            code7: codes.next().cloned(),
-            code8: codes.next().cloned(),
+            original_text: include_original.then(|| original_text.as_ref().unwrap_or(text).clone()),
+            code0: codes.next(),
+            code1: codes.next(),
+            code2: codes.next(),
+            code3: codes.next(),
+            code4: codes.next(),
+            code5: codes.next(),
+            code6: codes.next(),
+            code7: codes.next(),
+            code8: codes.next(),
+            excerpts: (!spans.is_empty()).then(|| {
+                spans
+                    .iter()
+                    .filter_map(|span| {
+                        Code::name_of(all_codes, span.code)
+                            .map(|name| format!("{}: \"{}\"", name, section.excerpt(span)))
+                    })
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            }),
+            memo: include_memos.then(|| memo.clone()),
+            interview_memo: include_memos.then(|| interview_memo.clone()),
+            coded_by: include_coders.then(|| coded_by(all_codes, section)),
         }
     }
 }
 
+/// A section and one of its codes, exported as one row per pair so that any number of codes can be
+/// exported.
+#[derive(serde::Serialize, Debug)]
+pub struct CsvLongSection {
+    interview: String,
+    /// the position of the section in the interview
+    section: usize,
+    speaker: String,
+    start: Option<String>,
+    end: Option<String>,
+    text: String,
+    /// the text as transcribed, only exported when asked for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    original_text: Option<String>,
+    /// sections without codes are exported once with no code
+    code: Option<String>,
+    /// the part of the text the code applies to, if not all of it
+    excerpt: Option<String>,
+    /// the memo on the section, only exported when asked for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memo: Option<String>,
+    /// the memo on the interview, exported along with the section memos
+    #[serde(skip_serializing_if = "Option::is_none")]
+    interview_memo: Option<String>,
+    /// who applied the code, only exported when asked for. Empty when it was not recorded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    coder: Option<String>,
+    /// when the coder applied the code, only exported when asked for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    coded_at: Option<String>,
+}
+
+impl CsvLongSection {
+    fn from_section(
+        Interview {
+            name,
+            speakers,
+            memo: interview_memo,
+            ..
+        }: &Interview,
+        codes: &[Code],
+        index: usize,
+        section @ Section {
+            speaker_id,
+            codes: section_codes,
+            text,
+            start,
+            end,
+            original_text,
+            spans,
+            memo,
+            applications,
+            ..
+        }: &Section,
+        Columns {
+            include_original,
+            include_memos,
+            include_coders,
+        }: Columns,
+    ) -> Vec<CsvLongSection> {
+        let row = |code: Option<&str>,
+                   excerpt: Option<&str>,
+                   application: Option<&CodeApplication>| CsvLongSection {
+            interview: name.clone(),
+            section: index,
+            speaker: speaker_name(speakers, *speaker_id),
+            start: start.map(format_timestamp),
+            end: end.map(format_timestamp),
+            text: text.clone(),
+            original_text: include_original.then(|| original_text.as_ref().unwrap_or(text).clone()),
+            code: code.map(String::from),
+            excerpt: excerpt.map(String::from),
+            memo: include_memos.then(|| memo.clone()),
+            interview_memo: include_memos.then(|| interview_memo.clone()),
+            coder: include_coders.then(|| {
+                application
+                    .map(|application| application.coder.clone())
+                    .unwrap_or_default()
+            }),
+            coded_at: include_coders.then(|| {
+                application
+                    .map(|application| format_unix_time(application.at))
+                    .unwrap_or_default()
+            }),
+        };
+        // with coders, a code is exported once for every coder who applied it
+        let applied_by = |id: CodeId| {
+            let applications = applications
+                .iter()
+                .filter(|application| include_coders && application.code == id)
+                .map(Some)
+                .collect::<Vec<_>>();
+            if applications.is_empty() {
+                vec![None]
+            } else {
+                applications
+            }
+        };
+        let rows = section_codes
+            .iter()
+            .filter_map(|id| Some((*id, Code::name_of(codes, *id)?)))
+            .flat_map(|(id, name)| {
+                applied_by(id)
+                    .into_iter()
+                    .map(move |application| row(Some(name), None, application))
+            })
+            .chain(spans.iter().filter_map(|span| {
+                Code::name_of(codes, span.code)
+                    .map(|name| row(Some(name), Some(section.excerpt(span)), None))
+            }))
+            .collect::<Vec<_>>();
+        if rows.is_empty() {
+            vec![row(None, None, None)]
+        } else {
+            rows
+        }
+    }
+}
+
+/// who applied each code to the whole section, as in "anger: alice, bob; calm: alice"
+fn coded_by(codes: &[Code], section: &Section) -> String {
+    section
+        .codes
+        .iter()
+        .filter_map(|id| {
+            let coders = section.coders_of(*id);
+            let name = Code::name_of(codes, *id)?;
+            (!coders.is_empty()).then(|| format!("{}: {}", name, coders.join(", ")))
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// the name of the speaker or an empty string if the speaker has been removed.
+fn speaker_name(speakers: &BTreeMap<u64, String>, speaker_id: u64) -> String {
+    speakers.get(&speaker_id).cloned().unwrap_or_default()
+}
+
 impl QualityQualitativeCoding {
     /// Called once before the first frame.
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         // Load previous app state (if any).
         if let Some(storage) = cc.storage {
-            eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default()
+            eframe::get_value::<Self>(storage, eframe::APP_KEY)
+                .unwrap_or_default()
+                .restored()
         } else {
             QualityQualitativeCoding::default()
         }
     }
 
-    fn try_update_codes(codes: &mut Vec<Code>, codes_recv: &mut Receiver<Vec<u8>>) {
+    /// the state as saved, with new codes kept clear of the ids its sections are coded with
+    fn restored(mut self) -> Self {
+        let sections = self
+            .interviews
+            .iter()
+            .flat_map(|swiper| &swiper.interview.sections);
+        self.next_code_id = self.next_code_id.max(CodeId::after_sections(sections));
+        self
+    }
+
+    /// Reads an uploaded codebook, which is imported once the user has chosen how.
+    fn try_update_codes(
+        pending_codebook: &mut Option<PendingCodebook>,
+        codes_recv: &mut Receiver<UploadedFile>,
+        notifications: &mut Notifications,
+    ) {
         match codes_recv.try_recv() {
-            Ok(bytes) => {
+            Ok(UploadedFile { name, bytes }) => {
                 match csv::Reader::from_reader(&bytes[..])
-                    .deserialize::<Code>()
-                    .into_iter()
+                    .deserialize::<CsvCode>()
                     .collect::<Result<Vec<_>, _>>()
                 {
                     Ok(record) => {
-                        *codes = record;
+                        *pending_codebook = Some(PendingCodebook::new(name, record));
                     }
                     Err(err) => {
-                        error!(error = ?err, "failed to parse csv");
+                        notifications.error(&format!("could not import codes from {}", name), err);
                     }
                 };
             }
@@ -270,24 +1902,148 @@ impl QualityQualitativeCoding {
     }
 
     fn try_update_interview(
-        interview: &mut Option<InterviewSwiper>,
-        receiver: &mut Receiver<Vec<u8>>,
+        interviews: &mut Vec<InterviewSwiper>,
+        current_interview: &mut usize,
+        receiver: &mut Receiver<UploadedFile>,
+        import_options: &ImportOptions,
+        notifications: &mut Notifications,
+    ) {
+        match receiver.try_recv() {
+            Ok(file) => match parse_interview::parse(&file.bytes, import_options) {
+                Ok(mut parsed_interview) => {
+                    parsed_interview.name = file.stem().to_string();
+                    tracing::trace!(?parsed_interview);
+                    interviews.push(InterviewSwiper::new(parsed_interview));
+                    *current_interview = interviews.len() - 1;
+                }
+                Err(err) => {
+                    notifications.error(&format!("could not import {}", file.name), err);
+                }
+            },
+            Err(TryRecvError::Empty) => { /* no file has been uploaded yet - no problem! */ }
+            Err(TryRecvError::Disconnected) => {
+                panic!("impossible to upload files. sender has been dropped.")
+            }
+        }
+    }
+
+    /// Turns the rows of a codebook csv into codes, see [Self::identify_imported_codes].
+    fn import_codebook(existing: &[Code], rows: Vec<CsvCode>, issued: CodeId) -> Vec<Code> {
+        let (imported, parents): (Vec<_>, Vec<_>) = rows
+            .into_iter()
+            .map(
+                |CsvCode {
+                     id,
+                     name,
+                     description,
+                     parent,
+                     color,
+                     definition,
+                     inclusion,
+                     exclusion,
+                     example,
+                 }| {
+                    let code = MaybeIdentifiedCode {
+                        id,
+                        name,
+                        description,
+                        parent: None,
+                        color,
+                        memo: String::new(),
+                        definition,
+                        inclusion,
+                        exclusion,
+                        example,
+                    };
+                    (code, parent)
+                },
+            )
+            .unzip();
+        let mut codes = Self::identify_imported_codes(existing, imported, issued);
+        let parents = parents
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, parent)| Some((index, parent?)))
+            .collect();
+        Code::set_parents_by_name(&mut codes, parents);
+        // memos are not part of the codebook csv so are kept from the codes they were written on
+        for code in &mut codes {
+            if let Some(existing) = existing.iter().find(|existing| existing.id == code.id) {
+                code.memo = existing.memo.clone();
+            }
+        }
+        codes
+    }
+
+    /// Gives every imported code an id. Codes keep the id of the existing code with the same id or,
+    /// failing that, the same name so that re-importing a codebook does not change any coding.
+    fn identify_imported_codes(
+        existing: &[Code],
+        imported: Vec<MaybeIdentifiedCode>,
+        issued: CodeId,
+    ) -> Vec<Code> {
+        let mut codes: Vec<Code> = Vec::with_capacity(imported.len());
+        for MaybeIdentifiedCode {
+            id,
+            name,
+            description,
+            parent,
+            color,
+            memo,
+            definition,
+            inclusion,
+            exclusion,
+            example,
+        } in imported
+        {
+            let id = id
+                .filter(|id| existing.iter().any(|code| code.id == *id))
+                .or_else(|| {
+                    existing
+                        .iter()
+                        .find(|code| code.name == name)
+                        .map(|code| code.id)
+                })
+                .filter(|id| codes.iter().all(|code| code.id != *id))
+                .unwrap_or_else(|| CodeId::next(&codes, CodeId::next(existing, issued)));
+            codes.push(Code {
+                id,
+                name,
+                description,
+                parent,
+                color,
+                memo,
+                definition,
+                inclusion,
+                exclusion,
+                example,
+            });
+        }
+        codes
+    }
+
+    fn try_update_project(
+        interviews: &mut Vec<InterviewSwiper>,
+        current_interview: &mut usize,
+        codes: &mut Vec<Code>,
+        next_code_id: &mut CodeId,
+        receiver: &mut Receiver<UploadedFile>,
+        notifications: &mut Notifications,
     ) {
         match receiver.try_recv() {
-            Ok(bytes) => {
+            Ok(UploadedFile { name, bytes }) => {
+                let context = format!("could not open {}", name);
                 match std::str::from_utf8(bytes.deref()) {
-                    Ok(utf8str) => match parse_interview::parse(utf8str) {
-                        Ok(parsed_interview) => {
-                            tracing::trace!(?parsed_interview);
-                            *interview = Some(InterviewSwiper::new(parsed_interview))
-                        }
-                        Err(err) => {
-                            tracing::trace!(error = ?err, "failed to parse json");
+                    Ok(utf8str) => match project::from_json(utf8str) {
+                        Ok(project) => {
+                            *interviews = project.interviews;
+                            *current_interview = project.current_interview;
+                            *codes = project.codes;
+                            *next_code_id = (*next_code_id).max(project.next_code_id);
                         }
+                        Err(err) => notifications.error(&context, err),
                     },
-                    Err(err) => {
-                        tracing::warn!(error = ?err, "failed to parse utf8");
-                    }
+                    Err(err) => notifications.error(&context, err),
                 };
             }
             Err(TryRecvError::Empty) => { /* no file has been uploaded yet - no problem! */ }
@@ -297,17 +2053,105 @@ impl QualityQualitativeCoding {
         }
     }
 
-    fn add_new_code(codes: &mut Vec<Code>, code_builder: &mut Code) {
-        let name = std::mem::take(&mut code_builder.name);
-        let description = std::mem::take(&mut code_builder.description);
-        codes.push(Code { name, description });
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_project(
+        interviews: &mut Vec<InterviewSwiper>,
+        current_interview: &mut usize,
+        codes: &mut Vec<Code>,
+        next_code_id: &mut CodeId,
+        project_path: &mut Option<PathBuf>,
+        _: &mut Sender<UploadedFile>,
+        notifications: &mut Notifications,
+    ) {
+        if let Some(path) = project::pick_open_path() {
+            match project::open(&path) {
+                Ok(project) => {
+                    info!(?path, "opened project");
+                    *interviews = project.interviews;
+                    *current_interview = project.current_interview;
+                    *codes = project.codes;
+                    *next_code_id = (*next_code_id).max(project.next_code_id);
+                    *project_path = Some(path);
+                }
+                Err(err) => {
+                    notifications.error(&format!("could not open {}", path.display()), err);
+                }
+            }
+        }
+    }
+
+    /// on the web the project is uploaded and handled by [Self::try_update_project]
+    #[cfg(target_arch = "wasm32")]
+    fn open_project(
+        _: &mut Vec<InterviewSwiper>,
+        _: &mut usize,
+        _: &mut Vec<Code>,
+        _: &mut CodeId,
+        _: &mut Option<PathBuf>,
+        project_tx: &mut Sender<UploadedFile>,
+        _: &mut Notifications,
+    ) {
+        file_upload::open_upload_dialog(project_tx.clone(), ("qqc project", &[project::EXTENSION]))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_project(
+        project: ProjectRef<'_>,
+        project_path: &mut Option<PathBuf>,
+        save_as: bool,
+        _: &mut bool,
+        notifications: &mut Notifications,
+    ) {
+        let path = match project_path {
+            Some(path) if !save_as => Some(path.clone()),
+            _ => project::pick_save_path(project.interviews),
+        };
+        if let Some(path) = path {
+            match project::save(&path, &project) {
+                Ok(()) => {
+                    info!(?path, "saved project");
+                    *project_path = Some(path);
+                }
+                Err(err) => {
+                    notifications.error(&format!("could not save to {}", path.display()), err);
+                }
+            }
+        }
+    }
+
+    /// on the web the project is downloaded from the save project window
+    #[cfg(target_arch = "wasm32")]
+    fn save_project(
+        _: ProjectRef<'_>,
+        _: &mut Option<PathBuf>,
+        _: bool,
+        save_project_open: &mut bool,
+        _: &mut Notifications,
+    ) {
+        *save_project_open = true;
+    }
+
+    /// Adds the code being built to the codebook. The next code is built under the same parent.
+    fn add_new_code(codes: &mut Vec<Code>, code_builder: &mut Code, issued: CodeId) {
+        let parent = code_builder.parent;
+        let code = std::mem::replace(
+            code_builder,
+            Code {
+                parent,
+                ..Code::default()
+            },
+        );
+        codes.push(Code {
+            id: CodeId::next(codes, issued),
+            ..code
+        });
     }
 
-    fn open_csv_upload_dialog(codes_tx: &mut Sender<Vec<u8>>) {
+    fn open_csv_upload_dialog(codes_tx: &mut Sender<UploadedFile>) {
         file_upload::open_upload_dialog(codes_tx.clone(), ("csv", &["csv"]))
     }
 
-    fn open_interview_upload_dialog(interview_tx: &mut Sender<Vec<u8>>) {
+    fn open_interview_upload_dialog(interview_tx: &mut Sender<UploadedFile>) {
         file_upload::open_upload_dialog(
             interview_tx.clone(),
             ("interview", parse_interview::file_extensions()),
@@ -330,39 +2174,240 @@ impl QualityQualitativeCoding {
 impl eframe::App for QualityQualitativeCoding {
     fn update(&mut self, ctx: &Context, _: &mut eframe::Frame) {
         self.handle_keyboard_shortcuts(ctx);
+        self.next_code_id = CodeId::next(&self.codes, self.next_code_id);
 
         let Self {
-            interview,
+            interviews,
+            current_interview,
             codes,
+            next_code_id,
             code_builder,
             interview_channel: (interview_tx, interview_rx),
             codes_channel: (codes_tx, codes_rx),
+            project_channel: (project_tx, project_rx),
+            project_path,
             settings,
             settings_open,
+            save_project_open,
             export_codes_open,
             export_interview_open,
+            export_memos_open,
+            import_options_open,
+            memos_open,
+            reliability_open,
+            reliability,
+            comparison,
+            merge_open,
+            project_merge,
+            coder_filter,
+            blind,
+            import_options,
+            codebook_import_mode,
+            pending_codebook,
+            export_interview_options,
+            export_codes_report,
+            export_interview_report,
+            export_memos_report,
+            notifications,
             speaker_builder,
+            correction,
         } = self;
 
-        Self::try_update_interview(interview, interview_rx);
-        Self::try_update_codes(codes, codes_rx);
+        Self::try_update_interview(
+            interviews,
+            current_interview,
+            interview_rx,
+            import_options,
+            notifications,
+        );
+        Self::try_update_codes(pending_codebook, codes_rx, notifications);
+        reliability.try_update(notifications);
+        project_merge.try_update(notifications);
+        Self::try_update_project(
+            interviews,
+            current_interview,
+            codes,
+            next_code_id,
+            project_rx,
+            notifications,
+        );
+
+        notifications.show(ctx);
+        *current_interview = (*current_interview).min(interviews.len().saturating_sub(1));
+        if !correction
+            .as_ref()
+            .map_or(true, |correction| correction.is_of(interviews))
+        {
+            *correction = None;
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        egui::Window::new("save project")
+            .open(save_project_open)
+            .show(ctx, |ui| {
+                match project::to_data_url(&ProjectRef::new(
+                    interviews,
+                    *current_interview,
+                    codes,
+                    *next_code_id,
+                )) {
+                    Ok(data_url) => ui.hyperlink_to("download project", data_url),
+                    Err(err) => {
+                        warn!(?err, "failed to turn project to data url");
+                        ui.label("failed")
+                    }
+                }
+            });
+
+        egui::Window::new("interview import options")
+            .open(import_options_open)
+            .show(ctx, |ui| {
+                ui.weak("applied to interviews imported from now on");
+                ui.checkbox(
+                    &mut import_options.merge_same_speaker,
+                    "merge consecutive sections by the same speaker",
+                );
+                ui.checkbox(
+                    &mut import_options.split_sentences,
+                    "give every sentence its own section",
+                );
+                ui.add_enabled_ui(!import_options.split_sentences, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.checkbox(
+                            &mut import_options.split_long_sections,
+                            "split sections between sentences once longer than",
+                        );
+                        ui.add(
+                            egui::DragValue::new(&mut import_options.max_section_length)
+                                .clamp_range(1..=usize::MAX),
+                        );
+                        ui.label("characters");
+                    });
+                });
+            });
+
+        let mut action = None;
+        if let Some(pending) = pending_codebook {
+            egui::Window::new("import codebook")
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    action = codebook_import::codebook_import_ui(
+                        ui,
+                        codes,
+                        *next_code_id,
+                        pending,
+                        codebook_import_mode,
+                    );
+                });
+        }
+        match action {
+            Some(CodebookImportAction::Import(import)) => {
+                info!(mode = ?codebook_import_mode, "importing codebook");
+                *codes = import.codes;
+                for section in interviews
+                    .iter_mut()
+                    .flat_map(|interview| &mut interview.interview.sections)
+                {
+                    section.retain_codes(|id| codes.iter().any(|code| code.id == *id));
+                }
+                *pending_codebook = None;
+            }
+            Some(CodebookImportAction::Cancel) => *pending_codebook = None,
+            None => {}
+        }
 
         egui::Window::new("export codes")
             .open(export_codes_open)
-            .show(ctx, |ui| export::export_codes(codes, ui));
+            .show(ctx, |ui| {
+                export::export_codes(codes, ui, export_codes_report)
+            });
 
         egui::Window::new("export interview")
             .open(export_interview_open)
-            .show(ctx, |ui| match interview {
-                None => ui.label("nothing to export"),
-                Some(InterviewSwiper { interview, .. }) => {
-                    export::export_interview(codes, ui, interview)
+            .show(ctx, |ui| {
+                let blind_coder = blind.then(|| settings.coder.as_str());
+                let visible = interviews
+                    .iter()
+                    .map(|swiper| Self::visible_interview(&swiper.interview, blind_coder))
+                    .collect::<Vec<_>>();
+                export::export_interview(
+                    codes,
+                    ui,
+                    visible.get(*current_interview).map(Cow::as_ref),
+                    &visible.iter().map(Cow::as_ref).collect::<Vec<_>>(),
+                    export_interview_options,
+                    export_interview_report,
+                )
+            });
+
+        egui::Window::new("export memos")
+            .open(export_memos_open)
+            .show(ctx, |ui| {
+                export::export_memos(
+                    codes,
+                    ui,
+                    &interviews
+                        .iter()
+                        .map(|swiper| &swiper.interview)
+                        .collect::<Vec<_>>(),
+                    export_memos_report,
+                )
+            });
+
+        egui::Window::new("inter-rater reliability")
+            .open(reliability_open)
+            .show(ctx, |ui| {
+                let reconcile = reliability.ui(
+                    ui,
+                    &interviews
+                        .iter()
+                        .map(|swiper| &swiper.interview)
+                        .collect::<Vec<_>>(),
+                    codes,
+                );
+                if reconcile.is_some() {
+                    *comparison = reconcile;
                 }
             });
 
+        egui::Window::new("merge projects")
+            .open(merge_open)
+            .show(ctx, |ui| {
+                if let Some(merged) = project_merge.ui(ui) {
+                    info!(conflicts = merged.conflicts.len(), "opened merged project");
+                    *interviews = merged.interviews;
+                    *current_interview = 0;
+                    *codes = merged.codes;
+                    *next_code_id = (*next_code_id).max(merged.next_code_id);
+                    *project_path = None;
+                    *comparison = None;
+                }
+            });
+
+        egui::Window::new("memos").open(memos_open).show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                Self::memos_ui(
+                    ui,
+                    codes,
+                    interviews.get_mut(*current_interview),
+                    blind.then(|| settings.coder.as_str()),
+                )
+            })
+        });
+
         egui::Window::new("settings")
             .open(settings_open)
             .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.heading("coder");
+                    ui.group(|ui| {
+                        ui.label("your name");
+                        ui.add_enabled(!*blind, egui::TextEdit::singleline(&mut settings.coder))
+                            .on_hover_text("recorded with each code you apply")
+                            .on_disabled_hover_text("finish blind coding to change your name");
+                    });
+                });
+                ui.add_space(20.0);
                 ui.horizontal(|ui| {
                     ui.heading("aesthetics");
                     ui.group(|ui| {
@@ -377,6 +2422,13 @@ impl eframe::App for QualityQualitativeCoding {
                         ui.label("number of segments after");
                         ui.add(number_changer(&mut settings.context_after))
                     });
+                    ui.group(|ui| {
+                        ui.label("highlight words with confidence below");
+                        ui.add(egui::Slider::new(
+                            &mut settings.confidence_threshold,
+                            0.0..=1.0,
+                        ))
+                    });
                 });
                 ui.add_space(20.0);
                 ui.horizontal(|ui| {
@@ -412,25 +2464,68 @@ impl eframe::App for QualityQualitativeCoding {
 
                     if ui
                         .button("reset")
-                        .on_hover_text("delete this invterview and start coding a new one")
+                        .on_hover_text("delete this interview and its coding")
                         .clicked()
+                        && *current_interview < interviews.len()
                     {
-                        *interview = None;
+                        interviews.remove(*current_interview);
+                        *current_interview = current_interview.saturating_sub(1);
                     }
                 })
             });
 
         egui::TopBottomPanel::top("top bar").show(ctx, |ui| {
             ui.horizontal(|ui| {
+                ui.menu_button("project", |ui| {
+                    if ui.button("open").clicked() {
+                        Self::open_project(
+                            interviews,
+                            current_interview,
+                            codes,
+                            next_code_id,
+                            project_path,
+                            project_tx,
+                            notifications,
+                        );
+                    }
+                    if ui.button("save").clicked() {
+                        Self::save_project(
+                            ProjectRef::new(interviews, *current_interview, codes, *next_code_id),
+                            project_path,
+                            false,
+                            save_project_open,
+                            notifications,
+                        );
+                    }
+                    if ui.button("save as").clicked() {
+                        Self::save_project(
+                            ProjectRef::new(interviews, *current_interview, codes, *next_code_id),
+                            project_path,
+                            true,
+                            save_project_open,
+                            notifications,
+                        );
+                    }
+                    if ui
+                        .button("merge")
+                        .on_hover_text("combine the project files of several coders")
+                        .clicked()
+                    {
+                        *merge_open = true;
+                    }
+                });
                 let export_menu_button = ui.menu_button("export", |ui| {
                     if !codes.is_empty() && ui.button("codes").clicked() {
                         *export_codes_open = true;
                     }
-                    if interview.is_some() && ui.button("interview").clicked() {
+                    if !interviews.is_empty() && ui.button("interview").clicked() {
                         *export_interview_open = true;
                     }
+                    if !interviews.is_empty() && ui.button("memos").clicked() {
+                        *export_memos_open = true;
+                    }
                 });
-                if codes.is_empty() && interview.is_none() {
+                if codes.is_empty() && interviews.is_empty() {
                     export_menu_button
                         .response
                         .on_hover_text("nothing to export");
@@ -439,7 +2534,52 @@ impl eframe::App for QualityQualitativeCoding {
                     if ui.button("codes").clicked() {
                         Self::open_csv_upload_dialog(codes_tx);
                     }
+                    if ui.button("interview").clicked() {
+                        Self::open_interview_upload_dialog(interview_tx);
+                    }
+                    if ui.button("interview options").clicked() {
+                        *import_options_open = true;
+                    }
+                });
+                ui.menu_button("compare", |ui| {
+                    if *blind {
+                        if ui
+                            .button("finish blind coding")
+                            .on_hover_text(
+                                "reveal the codes of other coders and compare them with yours",
+                            )
+                            .clicked()
+                        {
+                            *blind = false;
+                            reliability.compare_coder(
+                                &interviews
+                                    .iter()
+                                    .map(|swiper| &swiper.interview)
+                                    .collect::<Vec<_>>(),
+                                codes,
+                                &settings.coder,
+                            );
+                            *reliability_open = true;
+                        }
+                        return;
+                    }
+                    if ui.button("reliability").clicked() {
+                        *reliability_open = true;
+                    }
+                    let blind_button = ui
+                        .add_enabled(!settings.coder.is_empty(), egui::Button::new("code blind"))
+                        .on_hover_text("hide the codes of other coders from you until you finish")
+                        .on_disabled_hover_text("set your name in settings to code blind");
+                    if blind_button.clicked() {
+                        *blind = true;
+                        *reliability_open = false;
+                        *comparison = None;
+                        *coder_filter = None;
+                    }
                 });
+                if ui.button("memos").clicked() {
+                    *memos_open = true;
+                }
                 if ui.button("settings").clicked() {
                     *settings_open = true;
                 }
@@ -453,22 +2593,19 @@ impl eframe::App for QualityQualitativeCoding {
                     ui.label("no codes at the moment, try adding one or importing");
                 }
                 let mut codes_to_be_removed = Vec::new();
-                for (idx, Code { name, description }) in codes.iter_mut().enumerate() {
-                    ui.group(|ui| {
-                        ui.text_edit_singleline(name);
-                        ui.text_edit_singleline(description);
-                        if ui.button("remove").clicked() {
-                            codes_to_be_removed.push(idx)
-                        }
-                    });
-                }
-                codes_to_be_removed.reverse();
-                for idx in codes_to_be_removed {
-                    codes.remove(idx);
-                    if let Some(interview) = interview {
-                        for section in &mut interview.interview.sections {
-                            section.codes.remove(&idx);
-                        }
+                Self::code_tree_ui(ui, codes, None, &mut codes_to_be_removed);
+                for id in codes_to_be_removed {
+                    // the codes under a removed code move up to its parent rather than vanishing
+                    let parent = Code::parent_of(codes, id);
+                    for code in codes.iter_mut().filter(|code| code.parent == Some(id)) {
+                        code.parent = parent;
+                    }
+                    codes.retain(|code| code.id != id);
+                    for section in interviews
+                        .iter_mut()
+                        .flat_map(|interview| &mut interview.interview.sections)
+                    {
+                        section.retain_codes(|code| *code != id);
                     }
                 }
                 ui.heading("New Code");
@@ -476,23 +2613,56 @@ impl eframe::App for QualityQualitativeCoding {
                 ui.text_edit_singleline(&mut code_builder.name);
                 ui.label("description");
                 ui.text_edit_singleline(&mut code_builder.description);
+                let parents = Self::parent_choices(codes, None);
+                Self::parent_picker(ui, "new code", &mut code_builder.parent, &parents);
+                let color = Code {
+                    id: CodeId::next(codes, *next_code_id),
+                    ..Code::default()
+                }
+                .color();
+                Self::color_picker(ui, &mut code_builder.color, color);
                 if ui.button("add new code").clicked() {
                     info!(code = ?code_builder, "adding new code");
-                    Self::add_new_code(codes, code_builder);
+                    Self::add_new_code(codes, code_builder, *next_code_id);
                 }
             });
         });
 
+        if !interviews.is_empty() {
+            egui::SidePanel::left("interviews").show(ctx, |ui| {
+                ui.heading("Interviews");
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (idx, InterviewSwiper { interview, .. }) in interviews.iter().enumerate() {
+                        let name = if interview.name.is_empty() {
+                            "unnamed interview"
+                        } else {
+                            &interview.name
+                        };
+                        ui.selectable_value(current_interview, idx, name);
+                    }
+                });
+                if ui.button("add interview").clicked() {
+                    Self::open_interview_upload_dialog(interview_tx);
+                }
+            });
+        }
+
+        let interview = interviews.get_mut(*current_interview);
+
         if let Some(interview) = interview {
             egui::SidePanel::left("speaker and next").show(ctx, |ui| {
                 ui.horizontal(|ui| {
                     if ui.button("next").clicked() {
-                        interview.try_next();
+                        Self::step(interview, comparison.as_ref(), true);
                     }
                     if ui.button("prev").clicked() {
-                        interview.try_prev();
+                        Self::step(interview, comparison.as_ref(), false);
                     }
                 });
+                ui.group(|ui| {
+                    ui.label("interview name");
+                    ui.text_edit_singleline(&mut interview.interview.name);
+                });
                 ui.group(|ui| {
                     ui.label("speakers");
                     for name in interview.interview.speakers.values_mut() {
@@ -510,26 +2680,90 @@ impl eframe::App for QualityQualitativeCoding {
             });
 
             egui::TopBottomPanel::bottom("codes select").show(ctx, |ui| {
+                let text_id = section::primary_text_id(*current_interview, interview.index);
+                let name = interview.interview.name.clone();
+                let index = interview.index;
+                if *blind {
+                    ui.label(format!(
+                        "coding blind as {}, the codes of other coders are hidden until you finish",
+                        settings.coder
+                    ));
+                } else {
+                    let coders = Self::coders(&interview.interview.sections, &settings.coder);
+                    Self::coder_filter_ui(ui, coder_filter, &settings.coder, coders);
+                }
+                // someone else's codes are shown but can't be changed
+                let shown = coder_filter
+                    .as_deref()
+                    .filter(|shown| !*blind && *shown != settings.coder);
                 let current = interview.current_mut();
-                egui::Grid::new("code grid").show(ui, |ui| {
-                    for (idx, Code { name, description }) in codes.iter().enumerate() {
-                        if idx != 0 && idx % settings.code_columns == 0 {
-                            ui.end_row()
+                // excerpts are not recorded per coder, so blind coders code whole sections
+                let selection = section::selection(ctx, text_id, current).filter(|_| !*blind);
+                if let Some((start, end)) = selection {
+                    ui.horizontal(|ui| {
+                        ui.label(format!(
+                            "coding \"{}\"",
+                            char_slice(&current.text, start, end)
+                        ));
+                        if ui.button("code the whole section").clicked() {
+                            section::clear_selection(ctx, text_id);
                         }
-                        let checked = &mut current.codes.contains(&idx);
-                        let checkbox = ui.checkbox(checked, name).on_hover_text(description);
-                        if checkbox.changed() {
-                            if *checked {
-                                current.codes.insert(idx);
-                            } else {
-                                current.codes.remove(&idx);
+                    });
+                }
+                // the top-level codes followed by the codes under each theme
+                let groups = std::iter::once(None).chain(
+                    codes
+                        .iter()
+                        .map(|code| Some(code.id))
+                        .filter(|id| !Code::children_of(codes, *id).is_empty()),
+                );
+                for group in groups {
+                    if let Some(group) = group {
+                        let mut path = Code::ancestry(codes, group)
+                            .filter_map(|id| Code::name_of(codes, id))
+                            .collect::<Vec<_>>();
+                        path.reverse();
+                        ui.weak(path.join(" / "));
+                    }
+                    egui::Grid::new(("code grid", group)).show(ui, |ui| {
+                        for (idx, id) in Code::children_of(codes, group).into_iter().enumerate() {
+                            if idx != 0 && idx % settings.code_columns == 0 {
+                                ui.end_row()
+                            }
+                            if let Some(code) = codes.iter().find(|code| code.id == id) {
+                                let compared = comparison.as_ref().map(|comparison| {
+                                    (comparison, comparison.applied(&name, index, &code.name))
+                                });
+                                ui.horizontal(|ui| {
+                                    match shown {
+                                        Some(shown) => {
+                                            Self::others_code_ui(ui, current, shown, code)
+                                        }
+                                        None => Self::code_checkbox(
+                                            ui,
+                                            current,
+                                            selection,
+                                            &settings.coder,
+                                            *blind,
+                                            code,
+                                        ),
+                                    }
+                                    if coder_filter.is_none() && !*blind {
+                                        Self::other_coders_ui(ui, current, &settings.coder, code);
+                                    }
+                                    if let Some((comparison, Some(applied))) = compared {
+                                        Self::coders_ui(ui, comparison, applied);
+                                    }
+                                });
                             }
                         }
-                    }
-                });
+                    });
+                }
             });
         }
 
+        let interview = interviews.get_mut(*current_interview);
+
         egui::CentralPanel::default().show(ctx, |ui| match interview {
             None => {
                 ui.heading("quality qualitative coding");
@@ -544,6 +2778,9 @@ impl eframe::App for QualityQualitativeCoding {
                     ui.heading("Coding!");
                     ui.weak(format!("{} of {}", interview.index + 1, interview.interview.sections.len()));
                 });
+                let section_count = interview.interview.sections.len();
+                let blind_coder = blind.then(|| settings.coder.as_str());
+                let coder_filter = blind_coder.or(coder_filter.as_deref());
                 let (before, curr, after) = InterviewSwiper::window_mut(
                     &mut interview.interview.sections,
                     interview.index,
@@ -552,17 +2789,50 @@ impl eframe::App for QualityQualitativeCoding {
                 );
                 for section in before {
                     let section_response = ui.add(secondary_section(
-                        section,
+                        &Self::visible(section, blind_coder),
+                        codes,
                         &interview.interview.speakers[&section.speaker_id],
+                        coder_filter,
                     ));
                     if section_response.clicked() {}
                 }
 
+                let text_id = section::primary_text_id(*current_interview, interview.index);
                 let primary_section = ui.add(primary_section(
-                    curr,
+                    &Self::visible(curr, blind_coder),
+                    codes,
                     &interview.interview.speakers[&curr.speaker_id],
+                    settings.confidence_threshold,
+                    text_id,
+                    coder_filter,
                 ));
-                if ctx.input().key_pressed(
+                if blind_coder.is_none() {
+                    Self::coded_spans_ui(ui, codes, curr);
+                }
+                if let Some(compared) = comparison {
+                    let action = consensus::comparison_ui(
+                        ui,
+                        compared,
+                        codes,
+                        &interview.interview.name,
+                        interview.index,
+                        section_count,
+                        curr,
+                    );
+                    if action == ComparisonAction::Stop {
+                        *comparison = None;
+                    }
+                }
+                let restructure = Self::correction_ui(
+                    ui,
+                    correction,
+                    *current_interview,
+                    interview.index,
+                    section_count,
+                    curr,
+                    notifications,
+                );
+                if !Self::typing(ctx, Some(text_id)) && ctx.input().key_pressed(
                     settings
                         .shortcut_map
                         .get(&Action::SwapSpeaker)
@@ -578,10 +2848,21 @@ impl eframe::App for QualityQualitativeCoding {
                 }
                 for section in after {
                     ui.add(secondary_section(
-                        section,
+                        &Self::visible(section, blind_coder),
+                        codes,
                         &interview.interview.speakers[&section.speaker_id],
+                        coder_filter,
                     ));
                 }
+                if let Some(restructure) = restructure {
+                    Self::restructure(
+                        interview,
+                        restructure,
+                        correction,
+                        comparison,
+                        notifications,
+                    );
+                }
             }
         });
     }
@@ -593,3 +2874,696 @@ impl eframe::App for QualityQualitativeCoding {
 
 mod number_selector;
 mod section;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::reliability::Coding;
+
+    #[test]
+    fn new_codes_skip_ids_sections_are_coded_with() {
+        // saved before codes had ids, after removing the second of three codes
+        let state = r#"{
+    "codes": [
+        { "name": "first", "description": "" },
+        { "name": "third", "description": "" }
+    ],
+    "interview": {
+        "interview": {
+            "name": "",
+            "speakers": {},
+            "sections": [{ "speaker_id": 0, "text": "Hello", "codes": [2] }]
+        },
+        "index": 0
+    }
+}"#;
+        let mut app = serde_json::from_str::<QualityQualitativeCoding>(state)
+            .unwrap()
+            .restored();
+
+        QualityQualitativeCoding::add_new_code(
+            &mut app.codes,
+            &mut Code::default(),
+            app.next_code_id,
+        );
+
+        assert_eq!(app.codes[2].id, CodeId(3));
+    }
+
+    #[test]
+    fn codes_without_ids_are_given_their_position() {
+        let state = r#"{
+    "codes": [
+        { "name": "first", "description": "" },
+        { "name": "second", "description": "" }
+    ]
+}"#;
+        let app = serde_json::from_str::<QualityQualitativeCoding>(state).unwrap();
+
+        assert_eq!(
+            app.codes.iter().map(|code| code.id).collect::<Vec<_>>(),
+            vec![CodeId(0), CodeId(1)]
+        );
+    }
+
+    #[test]
+    fn long_export_has_a_row_per_code() {
+        let interview = Interview {
+            name: "princess bride".to_string(),
+            speakers: BTreeMap::from([(0, "Inigo Montoya".to_string())]),
+            sections: vec![],
+            ..Default::default()
+        };
+        let codes = (0..12)
+            .map(|id| Code {
+                id: CodeId(id),
+                name: id.to_string(),
+                description: "".to_string(),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        let section = Section {
+            speaker_id: 0,
+            text: "I am not left-handed".to_string(),
+            codes: (0..12).map(CodeId).chain([CodeId(99)]).collect(),
+            ..Default::default()
+        };
+
+        let rows =
+            CsvLongSection::from_section(&interview, &codes, 3, &section, Columns::default());
+
+        assert_eq!(rows.len(), 12);
+        assert!(rows
+            .iter()
+            .all(|row| row.section == 3 && row.interview == "princess bride"));
+        assert_eq!(rows[11].code.as_deref(), Some("11"));
+    }
+
+    #[test]
+    fn long_export_keeps_uncoded_sections() {
+        let section = Section {
+            speaker_id: 1,
+            text: "You are amazing".to_string(),
+            codes: BTreeSet::new(),
+            ..Default::default()
+        };
+
+        let rows = CsvLongSection::from_section(
+            &Interview::default(),
+            &[],
+            0,
+            &section,
+            Columns::default(),
+        );
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].code, None);
+        assert_eq!(rows[0].speaker, "");
+    }
+
+    #[test]
+    fn corrections_are_dropped_once_their_section_moves() {
+        let swiper = |texts: &[&str]| {
+            InterviewSwiper::new(Interview {
+                sections: texts
+                    .iter()
+                    .map(|text| Section {
+                        text: text.to_string(),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            })
+        };
+        let correction = Correction {
+            interview: 0,
+            section: 1,
+            replacing: "Prepare".to_string(),
+            text: "Prepare to die".to_string(),
+        };
+        let mut interviews = vec![swiper(&["Hello.", "Prepare", "to die"])];
+        assert!(correction.is_of(&interviews));
+
+        interviews[0].restructure(Restructure::MergeNext);
+        assert!(!correction.is_of(&interviews));
+
+        interviews = vec![swiper(&["Hello."])];
+        assert!(!correction.is_of(&interviews));
+        assert!(!correction.is_of(&[]));
+    }
+
+    #[test]
+    fn codes_without_a_coder_are_hidden_while_coding_blind() {
+        let id = CodeId(0);
+        let mut section = Section::default();
+        section.codes.insert(id);
+
+        assert!(QualityQualitativeCoding::unattributed(&section, id, false));
+        assert!(!QualityQualitativeCoding::unattributed(&section, id, true));
+        assert!(!section.coded_by(id, Some("Inigo")));
+        assert!(!QualityQualitativeCoding::visible(&section, Some("Inigo"))
+            .codes
+            .contains(&id));
+    }
+
+    #[test]
+    fn restructuring_stops_corrections_and_comparisons() {
+        let mut interview = InterviewSwiper::new(Interview {
+            sections: vec![Section::default(), Section::default()],
+            ..Default::default()
+        });
+        let mut correction = Some(Correction {
+            interview: 0,
+            section: 1,
+            replacing: "".to_string(),
+            text: "As you wish".to_string(),
+        });
+        let mut comparison = Some(Comparison::new(
+            ("Westley".to_string(), Coding::default()),
+            ("Buttercup".to_string(), Coding::default()),
+        ));
+        QualityQualitativeCoding::restructure(
+            &mut interview,
+            Restructure::MergeNext,
+            &mut correction,
+            &mut comparison,
+            &mut Notifications::default(),
+        );
+        assert_eq!(interview.interview.sections.len(), 1);
+        assert!(correction.is_none());
+        assert!(comparison.is_none());
+    }
+
+    #[test]
+    fn corrections_keep_the_transcript() {
+        let mut section = Section {
+            text: "I am not left-handed".to_string(),
+            ..Default::default()
+        };
+
+        section.correct("I am not right-handed".to_string());
+        section.correct("I am not left handed".to_string());
+
+        assert_eq!(section.text, "I am not left handed");
+        assert_eq!(
+            section.original_text.as_deref(),
+            Some("I am not left-handed")
+        );
+
+        section.correct("I am not left-handed".to_string());
+
+        assert_eq!(section.original_text, None);
+    }
+
+    #[test]
+    fn exports_can_include_the_transcript() {
+        let mut section = Section {
+            text: "I am not left-handed".to_string(),
+            ..Default::default()
+        };
+        section.correct("I am not right-handed".to_string());
+
+        let with = CsvSerializableSection::from_section(
+            &Interview::default(),
+            &[],
+            &section,
+            Columns {
+                include_original: true,
+                ..Default::default()
+            },
+        );
+        let without = CsvSerializableSection::from_section(
+            &Interview::default(),
+            &[],
+            &section,
+            Columns::default(),
+        );
+
+        assert_eq!(with.text, "I am not right-handed");
+        assert_eq!(with.original_text.as_deref(), Some("I am not left-handed"));
+        assert_eq!(without.original_text, None);
+    }
+
+    fn span(start: usize, end: usize, code: u64) -> CodedSpan {
+        CodedSpan {
+            start,
+            end,
+            code: CodeId(code),
+        }
+    }
+
+    #[test]
+    fn spans_follow_their_excerpt() {
+        let mut section = Section {
+            text: "Hello. Prepare to die".to_string(),
+            spans: [span(0, 6, 0), span(7, 14, 1), span(4, 10, 2)].into(),
+            ..Default::default()
+        };
+
+        let mut rest = section.split_off(6);
+
+        let excerpts = |section: &Section| {
+            section
+                .spans
+                .iter()
+                .map(|span| (section.excerpt(span).to_string(), span.code))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            excerpts(&section),
+            [
+                ("Hello.".to_string(), CodeId(0)),
+                ("o.".to_string(), CodeId(2))
+            ]
+        );
+        assert_eq!(
+            excerpts(&rest),
+            [
+                ("Pre".to_string(), CodeId(2)),
+                ("Prepare".to_string(), CodeId(1))
+            ]
+        );
+
+        assert_eq!(rest.correct("Please prepare to die".to_string()), 0);
+        section.merge(rest);
+
+        assert_eq!(section.text, "Hello. Please prepare to die");
+        assert_eq!(
+            excerpts(&section),
+            [
+                ("Hello.".to_string(), CodeId(0)),
+                ("o.".to_string(), CodeId(2)),
+                ("Please pre".to_string(), CodeId(2)),
+                ("Please prepare".to_string(), CodeId(1))
+            ]
+        );
+    }
+
+    #[test]
+    fn spans_stay_on_repeated_words() {
+        let mut section = Section {
+            text: "no no no".to_string(),
+            spans: [span(6, 8, 1), span(3, 5, 2)].into(),
+            ..Default::default()
+        };
+
+        assert_eq!(section.correct("no, no no".to_string()), 0);
+        assert_eq!(
+            section.spans,
+            BTreeSet::from([span(7, 9, 1), span(4, 6, 2)])
+        );
+
+        // the rewritten word stays coded
+        assert_eq!(section.correct("no, never no".to_string()), 0);
+        assert_eq!(
+            section.spans,
+            BTreeSet::from([span(10, 12, 1), span(4, 9, 2)])
+        );
+    }
+
+    #[test]
+    fn spans_whose_excerpt_is_gone_are_dropped() {
+        let mut section = Section {
+            text: "Hello. Prepare to die".to_string(),
+            spans: [
+                span(0, 6, 0),
+                span(7, 14, 1),
+                span(8, 11, 2),
+                span(4, 10, 3),
+            ]
+            .into(),
+            ..Default::default()
+        };
+
+        assert_eq!(section.correct("Hello. Ready to die".to_string()), 1);
+        let kept = BTreeSet::from([span(0, 6, 0), span(7, 12, 1), span(4, 7, 3)]);
+        assert_eq!(section.spans, kept);
+
+        assert_eq!(section.restore_original(), 0);
+        assert_eq!(section.text, "Hello. Prepare to die");
+        let restored = BTreeSet::from([span(0, 6, 0), span(7, 14, 1), span(4, 7, 3)]);
+        assert_eq!(section.spans, restored);
+    }
+
+    #[test]
+    fn long_export_has_a_row_per_excerpt() {
+        let codes = vec![Code {
+            id: CodeId(1),
+            name: "revenge".to_string(),
+            description: "".to_string(),
+            ..Default::default()
+        }];
+        let section = Section {
+            text: "Hello. Prepare to die".to_string(),
+            spans: [span(7, 21, 1)].into(),
+            ..Default::default()
+        };
+
+        let rows = CsvLongSection::from_section(
+            &Interview::default(),
+            &codes,
+            0,
+            &section,
+            Columns::default(),
+        );
+        let wide = CsvSerializableSection::from_section(
+            &Interview::default(),
+            &codes,
+            &section,
+            Columns::default(),
+        );
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].code.as_deref(), Some("revenge"));
+        assert_eq!(rows[0].excerpt.as_deref(), Some("Prepare to die"));
+        assert_eq!(
+            wide.excerpts.as_deref(),
+            Some("revenge: \"Prepare to die\"")
+        );
+    }
+
+    #[test]
+    fn single_interview_state_is_migrated() {
+        let state = r#"{
+    "interview": {
+        "interview": {
+            "speakers": { "0": "Inigo Montoya" },
+            "sections": [{ "speaker_id": 0, "text": "Hello", "codes": [1] }]
+        },
+        "index": 0
+    }
+}"#;
+        let app = serde_json::from_str::<QualityQualitativeCoding>(state).unwrap();
+
+        assert_eq!(app.interviews.len(), 1);
+        assert_eq!(
+            app.interviews[0].interview.sections[0].codes,
+            [CodeId(1)].into()
+        );
+    }
+
+    #[test]
+    fn ids_of_removed_codes_are_not_reused() {
+        let mut codes = vec![Code {
+            id: CodeId(0),
+            ..Default::default()
+        }];
+        let mut builder = Code::default();
+        let issued = CodeId::next(&codes, CodeId::default());
+        QualityQualitativeCoding::add_new_code(&mut codes, &mut builder, issued);
+        let issued = CodeId::next(&codes, issued);
+        codes.pop();
+
+        QualityQualitativeCoding::add_new_code(&mut codes, &mut builder, issued);
+
+        assert_eq!(codes.last().map(|code| code.id), Some(CodeId(2)));
+    }
+
+    #[test]
+    fn reimported_codes_keep_their_ids() {
+        let existing = vec![
+            Code {
+                id: CodeId(0),
+                name: "first".to_string(),
+                description: "".to_string(),
+                ..Default::default()
+            },
+            Code {
+                id: CodeId(3),
+                name: "second".to_string(),
+                description: "".to_string(),
+                ..Default::default()
+            },
+        ];
+        let imported =
+            csv::Reader::from_reader("name,description\nsecond,\nthird,\nfirst,\n".as_bytes())
+                .deserialize::<MaybeIdentifiedCode>()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+
+        let codes = QualityQualitativeCoding::identify_imported_codes(
+            &existing,
+            imported,
+            CodeId::default(),
+        );
+
+        assert_eq!(
+            codes
+                .iter()
+                .map(|code| (code.name.as_str(), code.id))
+                .collect::<Vec<_>>(),
+            vec![
+                ("second", CodeId(3)),
+                ("third", CodeId(4)),
+                ("first", CodeId(0))
+            ]
+        );
+    }
+
+    #[test]
+    fn codebooks_are_imported_with_their_parents() {
+        let rows = codebook_import::rows(
+            "name,description,parent
+emotion,,
+anger,,emotion
+rage,,anger
+loop,,loop
+orphan,,missing
+",
+        );
+
+        let codes = QualityQualitativeCoding::import_codebook(&[], rows, CodeId::default());
+
+        let parent = |name: &str| {
+            let code = codes.iter().find(|code| code.name == name).unwrap();
+            Code::parent_of(&codes, code.id).and_then(|parent| Code::name_of(&codes, parent))
+        };
+        assert_eq!(parent("emotion"), None);
+        assert_eq!(parent("anger"), Some("emotion"));
+        assert_eq!(parent("rage"), Some("anger"));
+        assert_eq!(parent("loop"), None);
+        assert_eq!(parent("orphan"), None);
+        assert_eq!(Code::root_of(&codes, codes[2].id), codes[0].id);
+    }
+
+    #[test]
+    fn codebooks_keep_their_colors() {
+        let rows = codebook_import::rows(
+            "name,description,color
+anger,,#E15759
+calm,,
+",
+        );
+
+        let codes = QualityQualitativeCoding::import_codebook(&[], rows, CodeId::default());
+
+        assert_eq!(codes[0].color(), CodeColor([0xe1, 0x57, 0x59]));
+        assert_eq!(codes[1].color(), CodeColor::PALETTE[1]);
+        let exported = codes
+            .iter()
+            .map(|code| CsvCode::from_code(&codes, code).color.unwrap().to_hex())
+            .collect::<Vec<_>>();
+        assert_eq!(exported, ["#e15759", "#f28e2b"]);
+        assert_eq!(CodeColor::parse("#e1575"), None);
+    }
+
+    #[test]
+    fn codebooks_keep_their_definitions() {
+        let rows = codebook_import::rows(
+            "name,description,definition,inclusion,exclusion,example
+anger,,feeling wronged,raised voices,\"frustration, not anger\",\"You killed my father\"
+calm,,,,,
+",
+        );
+
+        let codes = QualityQualitativeCoding::import_codebook(&[], rows, CodeId::default());
+        let csv = export_codes_csv(&codes);
+
+        assert_eq!(codes[0].inclusion, "raised voices");
+        assert_eq!(codes[1].definition, "");
+        assert_eq!(
+            csv,
+            "id,name,description,parent,color,definition,inclusion,exclusion,example
+0,anger,,,#4e79a7,feeling wronged,raised voices,\"frustration, not anger\",You killed my father
+1,calm,,,#f28e2b,,,,
+"
+        );
+        assert_eq!(
+            codes[0].hover_text(),
+            "definition: feeling wronged
+use when: raised voices
+do not use when: frustration, not anger
+e.g. You killed my father"
+        );
+    }
+
+    fn export_codes_csv(codes: &[Code]) -> String {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for code in codes {
+            writer.serialize(CsvCode::from_code(codes, code)).unwrap();
+        }
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn rolling_up_codes_their_theme() {
+        let codes = vec![
+            Code {
+                id: CodeId(0),
+                name: "emotion".to_string(),
+                ..Default::default()
+            },
+            Code {
+                id: CodeId(1),
+                name: "anger".to_string(),
+                parent: Some(CodeId(0)),
+                ..Default::default()
+            },
+            Code {
+                id: CodeId(2),
+                name: "rage".to_string(),
+                parent: Some(CodeId(1)),
+                ..Default::default()
+            },
+        ];
+        let section = Section {
+            codes: [CodeId(2)].into(),
+            spans: [span(0, 1, 1)].into(),
+            ..Default::default()
+        };
+
+        let rolled_up = section.rolled_up(&codes);
+
+        assert_eq!(rolled_up.codes, [CodeId(0)].into());
+        assert_eq!(rolled_up.spans, [span(0, 1, 0)].into());
+    }
+
+    #[test]
+    fn codes_stay_while_another_coder_applied_them() {
+        let mut section = Section::default();
+
+        section.apply(CodeId(1), "alice", 10);
+        section.apply(CodeId(1), "bob", 20);
+        section.apply(CodeId(2), "bob", 30);
+        section.apply(CodeId(1), "alice", 40);
+        section.unapply(CodeId(1), "alice");
+
+        assert_eq!(section.codes, [CodeId(1), CodeId(2)].into());
+        assert_eq!(section.coders_of(CodeId(1)), ["bob"]);
+        assert!(section.coded_by(CodeId(2), Some("bob")));
+        assert!(!section.coded_by(CodeId(2), Some("alice")));
+        assert!(section.coded_by(CodeId(2), None));
+
+        section.unapply(CodeId(1), "bob");
+
+        assert_eq!(section.codes, [CodeId(2)].into());
+    }
+
+    #[test]
+    fn blind_coders_only_see_their_own_codes() {
+        let mut section = Section {
+            codes: [CodeId(0)].into(),
+            spans: [span(0, 1, 1)].into(),
+            ..Default::default()
+        };
+        section.apply(CodeId(1), "alice", 0);
+        section.apply(CodeId(2), "bob", 0);
+        section.apply(CodeId(1), "bob", 0);
+
+        let seen = section.as_coded_by("bob");
+
+        assert_eq!(seen.codes, [CodeId(1), CodeId(2)].into());
+        assert_eq!(seen.coders_of(CodeId(1)), ["bob"]);
+        assert!(seen.spans.is_empty());
+        assert_eq!(section.as_coded_by("carol").codes, BTreeSet::new());
+    }
+
+    #[test]
+    fn blind_coders_export_only_their_own_codes() {
+        let codes = vec![
+            Code {
+                id: CodeId(1),
+                name: "anger".to_string(),
+                ..Default::default()
+            },
+            Code {
+                id: CodeId(2),
+                name: "calm".to_string(),
+                ..Default::default()
+            },
+        ];
+        let mut section = Section::default();
+        section.apply(CodeId(1), "alice", 0);
+        section.apply(CodeId(2), "bob", 0);
+        let interview = Interview {
+            sections: vec![section],
+            ..Default::default()
+        };
+        let columns = Columns {
+            include_coders: true,
+            ..Default::default()
+        };
+
+        let exported = QualityQualitativeCoding::visible_interview(&interview, Some("bob"));
+        let rows =
+            CsvLongSection::from_section(&exported, &codes, 0, &exported.sections[0], columns);
+        let wide =
+            CsvSerializableSection::from_section(&exported, &codes, &exported.sections[0], columns);
+
+        let coded = rows
+            .iter()
+            .map(|row| (row.code.as_deref(), row.coder.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(coded, [(Some("calm"), Some("bob"))]);
+        assert_eq!(wide.code0.as_deref(), Some("calm"));
+        assert_eq!(wide.code1, None);
+        assert_eq!(wide.coded_by.as_deref(), Some("calm: bob"));
+        let everyone = QualityQualitativeCoding::visible_interview(&interview, None);
+        assert_eq!(everyone.sections[0].codes, [CodeId(1), CodeId(2)].into());
+    }
+
+    #[test]
+    fn long_export_has_a_row_per_coder_of_a_code() {
+        let codes = vec![Code {
+            id: CodeId(1),
+            name: "anger".to_string(),
+            ..Default::default()
+        }];
+        let mut section = Section::default();
+        section.apply(CodeId(1), "alice", 0);
+        section.apply(CodeId(1), "bob", 1_000_000_000);
+
+        let rows = CsvLongSection::from_section(
+            &Interview::default(),
+            &codes,
+            0,
+            &section,
+            Columns {
+                include_coders: true,
+                ..Default::default()
+            },
+        );
+        let wide = CsvSerializableSection::from_section(
+            &Interview::default(),
+            &codes,
+            &section,
+            Columns {
+                include_coders: true,
+                ..Default::default()
+            },
+        );
+
+        let coders = rows
+            .iter()
+            .map(|row| (row.coder.as_deref(), row.coded_at.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            coders,
+            [
+                (Some("alice"), Some("1970-01-01T00:00:00Z")),
+                (Some("bob"), Some("2001-09-09T01:46:40Z")),
+            ]
+        );
+        assert_eq!(wide.coded_by.as_deref(), Some("anger: alice, bob"));
+    }
+}
@@ -0,0 +1,441 @@
+//! Importing a codebook csv into a codebook that may already be used to code interviews. Existing
+//! codes always keep their ids so the coding of every section stays attached to the same codes.
+
+use crate::app::{Code, CodeId, CsvCode, QualityQualitativeCoding};
+use egui::Ui;
+use std::collections::BTreeSet;
+
+/// How an imported codebook is combined with the current one.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Eq, PartialEq, Copy, Clone)]
+pub enum CodebookImportMode {
+    /// the imported codebook becomes the codebook, codes that are not in it are removed
+    Replace,
+    /// codes that are not in the codebook yet are added, the rest are left as they are
+    Append,
+    /// codes are matched by name, new codes are added and matched codes are updated
+    Merge,
+}
+
+impl Default for CodebookImportMode {
+    fn default() -> Self {
+        CodebookImportMode::Merge
+    }
+}
+
+/// A codebook csv waiting for the user to choose how to import it.
+#[derive(Debug)]
+pub struct PendingCodebook {
+    file_name: String,
+    rows: Vec<CsvCode>,
+}
+
+impl PendingCodebook {
+    pub fn new(file_name: String, rows: Vec<CsvCode>) -> Self {
+        Self { file_name, rows }
+    }
+}
+
+/// The codebook an import results in along with how it differs from the current one, described
+/// for the user to check before importing.
+#[derive(Debug, Default)]
+pub struct CodebookImport {
+    pub codes: Vec<Code>,
+    added: Vec<String>,
+    changed: Vec<String>,
+    conflicts: Vec<String>,
+    removed: Vec<String>,
+}
+
+/// What the user chose to do with a pending codebook.
+#[derive(Debug)]
+pub enum CodebookImportAction {
+    Import(CodebookImport),
+    Cancel,
+}
+
+/// What importing `rows` into the `existing` codes would do. New codes get ids from `issued` on,
+/// see [`CodeId::next`].
+pub fn plan(
+    existing: &[Code],
+    rows: &[CsvCode],
+    mode: CodebookImportMode,
+    issued: CodeId,
+) -> CodebookImport {
+    let mut import = CodebookImport::default();
+    let mut names = BTreeSet::new();
+    let mut rows = rows.to_vec();
+    rows.retain(|row| {
+        let first = names.insert(row.name.clone());
+        if !first {
+            import.conflicts.push(format!(
+                "{} is in the imported codebook more than once, only the first is imported",
+                row.name
+            ));
+        }
+        first
+    });
+    // the codes whose parent the import sets, by name
+    let mut parented = Vec::new();
+    match mode {
+        CodebookImportMode::Replace => {
+            parented = rows
+                .iter()
+                .filter_map(|row| Some((row.name.clone(), row.parent.clone()?)))
+                .collect();
+            import.codes = QualityQualitativeCoding::import_codebook(existing, rows, issued);
+            for code in &import.codes {
+                match existing.iter().find(|old| old.id == code.id) {
+                    None => import.added.push(code.name.clone()),
+                    Some(old) => {
+                        let fields = differences(existing, old, &import.codes, code);
+                        if !fields.is_empty() {
+                            import
+                                .changed
+                                .push(format!("{} ({})", old.name, fields.join(", ")));
+                        }
+                    }
+                }
+            }
+            import.removed = existing
+                .iter()
+                .filter(|old| import.codes.iter().all(|code| code.id != old.id))
+                .map(|old| old.name.clone())
+                .collect();
+        }
+        CodebookImportMode::Append | CodebookImportMode::Merge => {
+            let mut codes = existing.to_vec();
+            let mut parents = Vec::new();
+            for row in rows {
+                match codes.iter().position(|code| code.name == row.name) {
+                    Some(_) if mode == CodebookImportMode::Append => {
+                        import.conflicts.push(format!(
+                            "{} is already in the codebook so was left as it is",
+                            row.name
+                        ))
+                    }
+                    Some(index) => {
+                        let (changed, conflicting) = update(&mut codes, index, &row);
+                        if !changed.is_empty() {
+                            import
+                                .changed
+                                .push(format!("{} ({})", row.name, changed.join(", ")));
+                        }
+                        if !conflicting.is_empty() {
+                            import.conflicts.push(format!(
+                                "{} has a different {} in the imported codebook, which replaces it",
+                                row.name,
+                                conflicting.join(", ")
+                            ));
+                        }
+                        if let Some(parent) = &row.parent {
+                            parents.push((index, parent.clone()));
+                            parented.push((row.name.clone(), parent.clone()));
+                        }
+                    }
+                    None => {
+                        if let Some(parent) = &row.parent {
+                            parents.push((codes.len(), parent.clone()));
+                            parented.push((row.name.clone(), parent.clone()));
+                        }
+                        import.added.push(row.name.clone());
+                        codes.push(new_code(CodeId::next(&codes, issued), row));
+                    }
+                }
+            }
+            Code::set_parents_by_name(&mut codes, parents);
+            import.codes = codes;
+        }
+    }
+    for (name, parent) in parented {
+        let code = import.codes.iter().find(|code| code.name == name);
+        let placed = code
+            .and_then(|code| Code::parent_of(&import.codes, code.id))
+            .and_then(|id| Code::name_of(&import.codes, id))
+            == Some(parent.as_str());
+        if !placed {
+            import.conflicts.push(format!(
+                "{} could not be made a part of {}, which is missing or a part of it",
+                name, parent
+            ));
+        }
+    }
+    import
+}
+
+fn new_code(
+    id: CodeId,
+    CsvCode {
+        name,
+        description,
+        color,
+        definition,
+        inclusion,
+        exclusion,
+        example,
+        ..
+    }: CsvCode,
+) -> Code {
+    Code {
+        id,
+        name,
+        description,
+        color,
+        definition,
+        inclusion,
+        exclusion,
+        example,
+        ..Code::default()
+    }
+}
+
+/// Updates the code at `index` with the fields of `row` that are filled in, returning the fields
+/// that were empty before and those that had something else in them. The parent is set afterwards,
+/// once every imported code is in the codebook, but is reported here.
+fn update(
+    codes: &mut [Code],
+    index: usize,
+    row: &CsvCode,
+) -> (Vec<&'static str>, Vec<&'static str>) {
+    let mut changed = Vec::new();
+    let mut conflicting = Vec::new();
+    let mut compare = |field: &'static str, was_empty: bool, differs: bool| {
+        if differs {
+            if was_empty {
+                changed.push(field)
+            } else {
+                conflicting.push(field)
+            }
+        }
+    };
+    if let Some(parent) = &row.parent {
+        let current =
+            Code::parent_of(codes, codes[index].id).and_then(|id| Code::name_of(codes, id));
+        compare(
+            "parent",
+            current.is_none(),
+            current != Some(parent.as_str()),
+        );
+    }
+    let code = &mut codes[index];
+    if let Some(color) = row.color {
+        compare("color", code.color.is_none(), code.color() != color);
+        if code.color() != color {
+            code.color = Some(color);
+        }
+    }
+    for (field, current, imported) in [
+        ("description", &mut code.description, &row.description),
+        ("definition", &mut code.definition, &row.definition),
+        ("inclusion", &mut code.inclusion, &row.inclusion),
+        ("exclusion", &mut code.exclusion, &row.exclusion),
+        ("example", &mut code.example, &row.example),
+    ] {
+        if !imported.is_empty() {
+            compare(field, current.is_empty(), current != imported);
+            *current = imported.clone();
+        }
+    }
+    (changed, conflicting)
+}
+
+/// the fields that differ between a code before and after a replacing import
+fn differences(before: &[Code], old: &Code, after: &[Code], new: &Code) -> Vec<&'static str> {
+    let parent_name = |codes: &[Code], code: &Code| {
+        Code::parent_of(codes, code.id)
+            .and_then(|id| Code::name_of(codes, id))
+            .map(String::from)
+    };
+    [
+        ("name", old.name != new.name),
+        ("description", old.description != new.description),
+        (
+            "parent",
+            parent_name(before, old) != parent_name(after, new),
+        ),
+        ("color", old.color() != new.color()),
+        ("definition", old.definition != new.definition),
+        ("inclusion", old.inclusion != new.inclusion),
+        ("exclusion", old.exclusion != new.exclusion),
+        ("example", old.example != new.example),
+    ]
+    .into_iter()
+    .filter(|(_, differs)| *differs)
+    .map(|(field, _)| field)
+    .collect()
+}
+
+/// The choice of how to import a pending codebook along with a preview of what it would change.
+pub fn codebook_import_ui(
+    ui: &mut Ui,
+    codes: &[Code],
+    issued: CodeId,
+    PendingCodebook { file_name, rows }: &PendingCodebook,
+    mode: &mut CodebookImportMode,
+) -> Option<CodebookImportAction> {
+    ui.label(format!("importing {}", file_name));
+    ui.horizontal(|ui| {
+        ui.radio_value(mode, CodebookImportMode::Merge, "merge by name")
+            .on_hover_text("add new codes and update the codes with the same name");
+        ui.radio_value(mode, CodebookImportMode::Append, "add new codes")
+            .on_hover_text("add new codes and leave the codes with the same name as they are");
+        ui.radio_value(mode, CodebookImportMode::Replace, "replace")
+            .on_hover_text("replace the codebook, removing codes that are not in the import");
+    });
+    let import = plan(codes, rows, *mode, issued);
+    let warn = ui.visuals().warn_fg_color;
+    let error = ui.visuals().error_fg_color;
+    let lists = [
+        ("added", &import.added, None),
+        ("changed", &import.changed, None),
+        ("conflicting", &import.conflicts, Some(warn)),
+        (
+            "removed along with their coding",
+            &import.removed,
+            Some(error),
+        ),
+    ];
+    if lists.iter().all(|(_, list, _)| list.is_empty()) {
+        ui.label("the codebook is unchanged");
+    }
+    egui::ScrollArea::vertical()
+        .max_height(300.0)
+        .show(ui, |ui| {
+            for (heading, list, color) in lists {
+                if list.is_empty() {
+                    continue;
+                }
+                egui::CollapsingHeader::new(format!("{} {}", list.len(), heading))
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        for line in list {
+                            match color {
+                                Some(color) => ui.colored_label(color, line),
+                                None => ui.label(line),
+                            };
+                        }
+                    });
+            }
+        });
+    ui.horizontal(|ui| {
+        if ui.button("import").clicked() {
+            Some(CodebookImportAction::Import(import))
+        } else if ui.button("cancel").clicked() {
+            Some(CodebookImportAction::Cancel)
+        } else {
+            None
+        }
+    })
+    .inner
+}
+
+/// the rows of a codebook csv
+#[cfg(test)]
+pub(crate) fn rows(csv: &str) -> Vec<CsvCode> {
+    csv::Reader::from_reader(csv.as_bytes())
+        .deserialize::<CsvCode>()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn existing() -> Vec<Code> {
+        vec![
+            Code {
+                id: CodeId(3),
+                name: "revenge".to_string(),
+                description: "getting even".to_string(),
+                ..Default::default()
+            },
+            Code {
+                id: CodeId(7),
+                name: "handedness".to_string(),
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn names(codes: &[Code]) -> Vec<(&str, CodeId)> {
+        codes
+            .iter()
+            .map(|code| (code.name.as_str(), code.id))
+            .collect()
+    }
+
+    const IMPORTED: &str = "name,description,parent
+fencing,,
+handedness,which hand is used,fencing
+revenge,an eye for an eye,
+";
+
+    #[test]
+    fn merging_keeps_the_ids_of_codes_with_the_same_name() {
+        let import = plan(
+            &existing(),
+            &rows(IMPORTED),
+            CodebookImportMode::Merge,
+            CodeId::default(),
+        );
+
+        assert_eq!(
+            names(&import.codes),
+            [
+                ("revenge", CodeId(3)),
+                ("handedness", CodeId(7)),
+                ("fencing", CodeId(8))
+            ]
+        );
+        assert_eq!(import.codes[0].description, "an eye for an eye");
+        assert_eq!(Code::parent_of(&import.codes, CodeId(7)), Some(CodeId(8)));
+        assert_eq!(import.added, ["fencing"]);
+        assert_eq!(import.changed, ["handedness (parent, description)"]);
+        assert_eq!(
+            import.conflicts,
+            ["revenge has a different description in the imported codebook, which replaces it"]
+        );
+        assert!(import.removed.is_empty());
+    }
+
+    #[test]
+    fn appending_leaves_existing_codes_alone() {
+        let import = plan(
+            &existing(),
+            &rows(IMPORTED),
+            CodebookImportMode::Append,
+            CodeId::default(),
+        );
+
+        assert_eq!(
+            names(&import.codes),
+            [
+                ("revenge", CodeId(3)),
+                ("handedness", CodeId(7)),
+                ("fencing", CodeId(8))
+            ]
+        );
+        assert_eq!(import.codes[0].description, "getting even");
+        assert_eq!(Code::parent_of(&import.codes, CodeId(7)), None);
+        assert_eq!(import.added, ["fencing"]);
+        assert_eq!(import.conflicts.len(), 2);
+    }
+
+    #[test]
+    fn replacing_removes_codes_that_are_not_imported() {
+        let import = plan(
+            &existing(),
+            &rows("name,description\nrevenge,an eye for an eye\nrevenge,\n"),
+            CodebookImportMode::Replace,
+            CodeId::default(),
+        );
+
+        assert_eq!(names(&import.codes), [("revenge", CodeId(3))]);
+        assert_eq!(import.changed, ["revenge (description)"]);
+        assert_eq!(import.removed, ["handedness"]);
+        assert_eq!(
+            import.conflicts,
+            ["revenge is in the imported codebook more than once, only the first is imported"]
+        );
+    }
+}
@@ -0,0 +1,280 @@
+//! Stepping through the sections two coders coded differently so that they can agree on a
+//! consensus coding, which is recorded in the project as the codes of the [`CONSENSUS`] coder.
+
+use crate::app::reliability::Coding;
+use crate::app::{now, Code, Section};
+use egui::Ui;
+use std::collections::BTreeSet;
+
+/// The coder the agreed codes are applied by, leaving the codes of the coders being compared as
+/// they were.
+pub const CONSENSUS: &str = "consensus";
+
+/// Where a disagreement stands once the coders have met about it. Unresolved is ordered after
+/// discussed so that merging sections keeps a section unresolved if either part was.
+#[derive(
+    serde::Deserialize, serde::Serialize, Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq,
+)]
+pub enum Review {
+    Discussed,
+    Unresolved,
+}
+
+impl Review {
+    pub fn label(self) -> &'static str {
+        match self {
+            Review::Discussed => "discussed",
+            Review::Unresolved => "unresolved",
+        }
+    }
+}
+
+/// Two codings being reconciled, each along with where it came from.
+#[derive(Debug)]
+pub struct Comparison {
+    first: (String, Coding),
+    second: (String, Coding),
+}
+
+impl Comparison {
+    pub fn new(first: (String, Coding), second: (String, Coding)) -> Self {
+        Self { first, second }
+    }
+
+    /// the codes each coder applied to a section, if both coded it
+    fn codes_of(&self, interview: &str, index: usize) -> Option<[&BTreeSet<String>; 2]> {
+        fn codes<'a>(
+            coding: &'a Coding,
+            interview: &str,
+            index: usize,
+        ) -> Option<&'a BTreeSet<String>> {
+            coding.interviews.get(interview)?.get(index)
+        }
+        Some([
+            codes(&self.first.1, interview, index)?,
+            codes(&self.second.1, interview, index)?,
+        ])
+    }
+
+    /// whether the coders applied different codes to a section
+    pub fn differs(&self, interview: &str, index: usize) -> bool {
+        self.codes_of(interview, index)
+            .map_or(false, |[first, second]| first != second)
+    }
+
+    /// whether each coder applied `code` to a section, if both coded it
+    pub fn applied(&self, interview: &str, index: usize, code: &str) -> Option<[bool; 2]> {
+        let [first, second] = self.codes_of(interview, index)?;
+        Some([first.contains(code), second.contains(code)])
+    }
+
+    pub fn names(&self) -> [&str; 2] {
+        [&self.first.0, &self.second.0]
+    }
+}
+
+/// What to do with the comparison once this frame has been shown.
+#[derive(Debug, Eq, PartialEq)]
+pub enum ComparisonAction {
+    Continue,
+    Stop,
+}
+
+/// The codes of both coders for the section being coded along with ways to take either of them,
+/// or a mix, as the consensus and to mark how the discussion went.
+pub fn comparison_ui(
+    ui: &mut Ui,
+    comparison: &Comparison,
+    codes: &[Code],
+    interview: &str,
+    index: usize,
+    sections: usize,
+    section: &mut Section,
+) -> ComparisonAction {
+    let disagreements = (0..sections)
+        .filter(|index| comparison.differs(interview, *index))
+        .count();
+    let [first_name, second_name] = comparison.names();
+    let mut action = ComparisonAction::Continue;
+    ui.group(|ui| {
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "comparing {} and {}, {} sections differ",
+                first_name, second_name, disagreements
+            ));
+            if ui.button("stop comparing").clicked() {
+                action = ComparisonAction::Stop;
+            }
+        });
+        let [first, second] = match comparison.codes_of(interview, index) {
+            Some(codes) => codes,
+            None => {
+                ui.weak("this section was not coded by both coders");
+                return;
+            }
+        };
+        if first == second {
+            ui.weak("the coders agree on this section");
+        }
+        for (name, coded) in [(first_name, first), (second_name, second)] {
+            ui.horizontal(|ui| {
+                ui.strong(name);
+                ui.label(coded.iter().cloned().collect::<Vec<_>>().join(", "));
+            });
+        }
+        let mut consensus = None;
+        ui.horizontal(|ui| {
+            ui.label("take").on_hover_text(format!(
+                "apply the codes as the {} coder, leaving the codes of both coders as they are",
+                CONSENSUS
+            ));
+            if ui.button(first_name).clicked() {
+                consensus = Some(first.clone());
+            }
+            if ui.button(second_name).clicked() {
+                consensus = Some(second.clone());
+            }
+            if ui
+                .button("both")
+                .on_hover_text("every code either coder applied")
+                .clicked()
+            {
+                consensus = Some(first.union(second).cloned().collect());
+            }
+            if ui
+                .button("agreed")
+                .on_hover_text("only the codes both coders applied")
+                .clicked()
+            {
+                consensus = Some(first.intersection(second).cloned().collect());
+            }
+        });
+        if let Some(consensus) = consensus {
+            take(codes, section, &consensus, now());
+        }
+        let missing = first
+            .union(second)
+            .filter(|name| codes.iter().all(|code| &code.name != *name))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("not in the codebook: {}", missing.join(", ")),
+            );
+        }
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut section.review, None, "not reviewed");
+            for review in [Review::Discussed, Review::Unresolved] {
+                ui.selectable_value(&mut section.review, Some(review), review.label());
+            }
+        });
+    });
+    action
+}
+
+/// Makes the named codes the consensus on the section, leaving out names that are not in the
+/// codebook. Codes on the section without a coder belong to no one, so they are neither taken nor
+/// taken back.
+fn take(codes: &[Code], section: &mut Section, names: &BTreeSet<String>, at: u64) {
+    let unattributed = section
+        .codes
+        .iter()
+        .copied()
+        .filter(|id| section.coders_of(*id).is_empty())
+        .collect::<BTreeSet<_>>();
+    let taken = section.as_coded_by(CONSENSUS).codes;
+    for id in taken {
+        section.unapply(id, CONSENSUS);
+    }
+    for code in codes.iter().filter(|code| names.contains(&code.name)) {
+        if !unattributed.contains(&code.id) {
+            section.apply(code.id, CONSENSUS, at);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::reliability::coding;
+    use crate::app::CodeId;
+
+    #[test]
+    fn sections_differ_when_the_coders_applied_different_codes() {
+        let comparison = Comparison::new(
+            ("first".to_string(), coding(&[&["anger"], &["calm"], &[]])),
+            ("second".to_string(), coding(&[&["anger"], &[], &[]])),
+        );
+
+        let differing = (0..4)
+            .filter(|index| comparison.differs("interview", *index))
+            .collect::<Vec<_>>();
+
+        assert_eq!(differing, [1]);
+        assert!(!comparison.differs("another interview", 1));
+        assert_eq!(
+            comparison.applied("interview", 1, "calm"),
+            Some([true, false])
+        );
+    }
+
+    #[test]
+    fn taking_codes_leaves_out_those_not_in_the_codebook() {
+        let codes = vec![Code {
+            id: CodeId(5),
+            name: "anger".to_string(),
+            ..Default::default()
+        }];
+        let mut section = Section::default();
+
+        take(
+            &codes,
+            &mut section,
+            &["anger".to_string(), "rage".to_string()].into(),
+            0,
+        );
+
+        assert_eq!(section.codes, [CodeId(5)].into());
+    }
+
+    #[test]
+    fn taking_codes_leaves_codes_without_a_coder_alone() {
+        let codes = [1, 2].map(|id| Code {
+            id: CodeId(id),
+            name: id.to_string(),
+            ..Default::default()
+        });
+        let mut section = Section {
+            codes: [CodeId(1)].into(),
+            ..Default::default()
+        };
+
+        take(&codes, &mut section, &["1".to_string()].into(), 1);
+        take(&codes, &mut section, &["2".to_string()].into(), 2);
+
+        assert_eq!(section.codes, [CodeId(1), CodeId(2)].into());
+        assert_eq!(section.coders_of(CodeId(1)), Vec::<&str>::new());
+        assert_eq!(section.as_coded_by(CONSENSUS).codes, [CodeId(2)].into());
+    }
+
+    #[test]
+    fn taking_codes_leaves_the_coders_codes_alone() {
+        let codes = [1, 2, 3].map(|id| Code {
+            id: CodeId(id),
+            name: id.to_string(),
+            ..Default::default()
+        });
+        let mut section = Section::default();
+        section.apply(CodeId(1), "first", 0);
+        section.apply(CodeId(2), "second", 0);
+
+        take(&codes, &mut section, &["1".to_string()].into(), 1);
+        take(&codes, &mut section, &["2".to_string()].into(), 2);
+
+        assert_eq!(section.codes, [CodeId(1), CodeId(2)].into());
+        assert_eq!(section.as_coded_by("first").codes, [CodeId(1)].into());
+        assert_eq!(section.as_coded_by("second").codes, [CodeId(2)].into());
+        assert_eq!(section.as_coded_by(CONSENSUS).codes, [CodeId(2)].into());
+    }
+}
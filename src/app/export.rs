@@ -1,11 +1,65 @@
-use crate::app::{Code, CsvSerializableSection, Interview};
+use crate::app::section::format_timestamp;
+use crate::app::{
+    coded_by, speaker_name, Code, CsvCode, CsvLongSection, CsvSerializableSection, Interview,
+    Section,
+};
 use csv::Writer;
 use egui::{Response, Ui};
 use serde::Serialize;
+use std::borrow::Cow;
 use std::error::Error;
 use std::fs::File;
 use std::io;
-use tracing::warn;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// The shape of the interview csv.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Eq, PartialEq, Copy, Clone)]
+pub enum InterviewExportFormat {
+    /// one row per section with a column for each of its codes
+    Wide,
+    /// one row per section and code pair (tidy data)
+    Long,
+    /// one row per section with a 0/1 column for every code in the codebook
+    Matrix,
+}
+
+/// Which interviews to export.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Eq, PartialEq, Copy, Clone)]
+pub enum InterviewExportScope {
+    /// the interview being coded
+    Current,
+    /// every interview in the project
+    All,
+}
+
+/// How the last interview export was set up, kept for the next one.
+#[derive(serde::Deserialize, serde::Serialize, Default, Debug)]
+#[serde(default)]
+pub struct InterviewExportOptions {
+    format: InterviewExportFormat,
+    scope: InterviewExportScope,
+    /// add a column with the text as transcribed
+    include_original: bool,
+    /// export the top-level theme of each code in place of the code itself
+    roll_up: bool,
+    /// add columns with the memo on each section and on its interview
+    include_memos: bool,
+    /// add columns with who applied each code and when
+    include_coders: bool,
+}
+
+impl Default for InterviewExportScope {
+    fn default() -> Self {
+        InterviewExportScope::Current
+    }
+}
+
+impl Default for InterviewExportFormat {
+    fn default() -> Self {
+        InterviewExportFormat::Wide
+    }
+}
 
 #[cfg(target_arch = "wasm32")]
 fn to_data_url_csv<T: Serialize>(iter: impl Iterator<Item = T>) -> Result<String, Box<dyn Error>> {
@@ -22,92 +76,567 @@ fn to_csv<W: io::Write, I: Serialize>(
     iterator.try_for_each(|record| writer.serialize(record))?;
     Ok(writer)
 }
+
 #[cfg(target_arch = "wasm32")]
-fn export_web(
-    codes: &[Code],
-    ui: &mut Ui,
-    Interview { speakers, sections }: &Interview,
-) -> Response {
-    match to_data_url_csv(
-        sections
-            .iter()
-            .map(|section| CsvSerializableSection::from_section(speakers, codes, section)),
-    ) {
+fn export_web<T: Serialize>(ui: &mut Ui, rows: impl Iterator<Item = T>) -> Response {
+    match to_data_url_csv(rows) {
         Ok(data_url) => ui.hyperlink_to("download csv", data_url),
         Err(err) => {
-            warn!(err, "failed to turn interview to data url");
+            warn!(err, "failed to turn rows to data url");
             ui.label("failed")
         }
     }
 }
 
+/// The outcome of the last native export, shown until the next one.
+#[derive(Debug, Default)]
+pub struct ExportReport(Option<Result<PathBuf, String>>);
+
 #[cfg(not(target_arch = "wasm32"))]
-fn export_native(codes: &[Code], ui: &mut Ui, interview: &Interview) -> Response {
-    match write_to_file(codes, interview) {
-        Ok(()) => ui.label("wrote to file"),
-        Err(err) => {
-            warn!(err, "failed to write to file");
-            ui.label("failed to write to file")
+impl ExportReport {
+    /// Remembers how writing the export to `path` went.
+    fn record(&mut self, path: PathBuf, written: Result<(), Box<dyn Error>>) {
+        self.0 = Some(match written {
+            Ok(()) => {
+                info!(?path, "exported csv");
+                Ok(path)
+            }
+            Err(err) => {
+                warn!(err, "failed to write to file");
+                Err(err.to_string())
+            }
+        });
+    }
+
+    /// what to tell the user about the last export, if there was one, and whether it failed
+    fn message(&self) -> Option<(String, bool)> {
+        match &self.0 {
+            None => None,
+            Some(Ok(path)) => Some((format!("saved to {}", path.display()), false)),
+            Some(Err(err)) => Some((format!("failed to save: {}", err), true)),
         }
     }
 }
 
-fn write_to_file(
-    codes: &[Code],
-    Interview { speakers, sections }: &Interview,
+#[cfg(not(target_arch = "wasm32"))]
+fn export_native<T: Serialize>(
+    ui: &mut Ui,
+    file_name: &str,
+    report: &mut ExportReport,
+    rows: impl Iterator<Item = T>,
+) -> Response {
+    let response = ui.button("save as…");
+    if response.clicked() {
+        // the native save dialogs ask the user to confirm before overwriting an existing file
+        let path = rfd::FileDialog::new()
+            .add_filter("csv", &["csv"])
+            .set_file_name(file_name)
+            .save_file();
+        if let Some(path) = path {
+            let written = write_to_file(&path, rows);
+            report.record(path, written);
+        }
+    }
+    match report.message() {
+        None => {}
+        Some((message, false)) => {
+            ui.label(message);
+        }
+        Some((message, true)) => {
+            ui.colored_label(ui.visuals().error_fg_color, message);
+        }
+    }
+    response
+}
+
+fn write_to_file<T: Serialize>(
+    path: &Path,
+    rows: impl Iterator<Item = T>,
 ) -> Result<(), Box<dyn Error>> {
-    let file = File::options()
-        .create(true)
-        .write(true)
-        .open("export_interview.csv")?;
-    Ok(to_csv(
-        file,
-        sections
-            .iter()
-            .map(|section| CsvSerializableSection::from_section(speakers, codes, section)),
-    )?
-    .flush()?)
+    let file = File::create(path)?;
+    Ok(to_csv(file, rows)?.flush()?)
 }
 
-pub fn export_interview(codes: &[Code], ui: &mut Ui, interview: &Interview) -> Response {
+/// on the web the rows are downloaded from a link, so there is no file name to suggest or report
+#[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+pub fn export_rows<T: Serialize>(
+    ui: &mut Ui,
+    file_name: &str,
+    report: &mut ExportReport,
+    rows: impl Iterator<Item = T>,
+) -> Response {
     #[cfg(target_arch = "wasm32")]
-    return export_web(codes, ui, interview);
+    {
+        export_web(ui, rows)
+    }
     #[cfg(not(target_arch = "wasm32"))]
-    return export_native(codes, ui, interview);
+    export_native(ui, file_name, report, rows)
 }
 
-pub fn export_codes(codes: &[Code], ui: &mut Ui) -> Response {
-    #[cfg(target_arch = "wasm32")]
-    return export_codes_web(codes, ui);
-    #[cfg(not(target_arch = "wasm32"))]
-    return export_codes_native(codes, ui);
+pub fn export_interview(
+    codes: &[Code],
+    ui: &mut Ui,
+    current: Option<&Interview>,
+    all: &[&Interview],
+    InterviewExportOptions {
+        format,
+        scope,
+        include_original,
+        roll_up,
+        include_memos,
+        include_coders,
+    }: &mut InterviewExportOptions,
+    report: &mut ExportReport,
+) -> Response {
+    ui.horizontal(|ui| {
+        ui.radio_value(scope, InterviewExportScope::Current, "this interview");
+        ui.radio_value(scope, InterviewExportScope::All, "all interviews");
+    });
+    ui.horizontal(|ui| {
+        ui.radio_value(format, InterviewExportFormat::Wide, "one row per section");
+        ui.radio_value(format, InterviewExportFormat::Long, "one row per code");
+        ui.radio_value(format, InterviewExportFormat::Matrix, "code matrix");
+    });
+    ui.checkbox(include_original, "include the original transcript")
+        .on_hover_text("add a column with the text as transcribed, before any corrections");
+    ui.checkbox(include_memos, "include memos")
+        .on_hover_text("add columns with the memo on each section and on its interview");
+    ui.checkbox(include_coders, "include who coded what")
+        .on_hover_text(
+            "add columns with the coders who applied each code and, one row per code, when",
+        );
+    ui.checkbox(roll_up, "roll codes up to their top-level theme")
+        .on_hover_text("export the top-level theme of each code rather than the code itself");
+    let columns = Columns {
+        include_original: *include_original,
+        include_memos: *include_memos,
+        include_coders: *include_coders,
+    };
+    let roll_up = *roll_up;
+    let top_level;
+    let codebook = if roll_up {
+        top_level = codes
+            .iter()
+            .filter(|code| Code::parent_of(codes, code.id).is_none())
+            .cloned()
+            .collect::<Vec<_>>();
+        top_level.as_slice()
+    } else {
+        codes
+    };
+    let (interviews, name) = match (scope, &current) {
+        (InterviewExportScope::Current, Some(interview)) if !interview.name.is_empty() => {
+            (std::slice::from_ref(interview), interview.name.as_str())
+        }
+        (InterviewExportScope::Current, Some(interview)) => {
+            (std::slice::from_ref(interview), "interview")
+        }
+        (InterviewExportScope::Current, None) => return ui.label("nothing to export"),
+        (InterviewExportScope::All, _) => (all, "interviews"),
+    };
+    let crowded = crowded_sections(interviews, roll_up.then(|| codes));
+    match format {
+        InterviewExportFormat::Wide if crowded > 0 => ui.colored_label(
+            ui.visuals().warn_fg_color,
+            format!(
+                "{} sections have more than {} codes, which don't fit one row per section. \
+                Export one row per code or the code matrix to keep every code.",
+                crowded,
+                CsvSerializableSection::CODE_COLUMNS
+            ),
+        ),
+        InterviewExportFormat::Wide => export_rows(
+            ui,
+            &format!("{}.csv", name),
+            report,
+            interviews.iter().flat_map(|interview| {
+                sections(interview, roll_up.then(|| codes)).map(move |section| {
+                    CsvSerializableSection::from_section(interview, codebook, &section, columns)
+                })
+            }),
+        ),
+        InterviewExportFormat::Long => export_rows(
+            ui,
+            &format!("{}_long.csv", name),
+            report,
+            interviews.iter().flat_map(|interview| {
+                sections(interview, roll_up.then(|| codes))
+                    .enumerate()
+                    .flat_map(move |(index, section)| {
+                        CsvLongSection::from_section(interview, codebook, index, &section, columns)
+                    })
+            }),
+        ),
+        InterviewExportFormat::Matrix => export_rows(
+            ui,
+            &format!("{}_matrix.csv", name),
+            report,
+            code_matrix(codebook, interviews, columns, roll_up.then(|| codes)),
+        ),
+    }
 }
 
-fn write_codes_to_file(codes: &[Code]) -> Result<(), Box<dyn Error>> {
-    let file = File::options()
-        .create(true)
-        .write(true)
-        .open("export_codes.csv")?;
-    Ok(to_csv(file, codes.iter())?.flush()?)
+/// The optional columns of an interview export.
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct Columns {
+    pub(crate) include_original: bool,
+    pub(crate) include_memos: bool,
+    pub(crate) include_coders: bool,
 }
 
-fn export_codes_native(codes: &[Code], ui: &mut Ui) -> Response {
-    match write_codes_to_file(codes) {
-        Ok(()) => ui.label("created file export_codes.csv"),
-        Err(err) => {
-            warn!(?err);
-            ui.label("failed to write codes to file")
-        }
-    }
+/// A header followed by a row for every section where each code has a column containing 1 if the
+/// section, or part of it, was coded with it and 0 otherwise. If the codes are rolled up, the
+/// sections are coded against `codes` with the full codebook given in `roll_up`.
+fn code_matrix<'a>(
+    codes: &'a [Code],
+    interviews: &'a [&'a Interview],
+    columns: Columns,
+    roll_up: Option<&'a [Code]>,
+) -> impl Iterator<Item = Vec<String>> + 'a {
+    let header = ["interview", "section", "speaker", "start", "end", "text"]
+        .into_iter()
+        .chain(columns.include_original.then(|| "original_text"))
+        .chain(columns.include_memos.then(|| "memo"))
+        .chain(columns.include_memos.then(|| "interview_memo"))
+        .chain(columns.include_coders.then(|| "coded_by"))
+        .map(String::from)
+        .chain(codes.iter().map(|Code { name, .. }| name.clone()))
+        .collect();
+    let rows = interviews.iter().flat_map(move |interview| {
+        sections(interview, roll_up)
+            .enumerate()
+            .map(move |(index, section)| {
+                code_matrix_row(codes, interview, index, &section, columns)
+            })
+    });
+    std::iter::once(header).chain(rows)
 }
 
-#[cfg(target_arch = "wasm32")]
-fn export_codes_web(codes: &[Code], ui: &mut Ui) -> Response {
-    match to_data_url_csv(codes.iter()) {
-        Ok(data_url) => ui.hyperlink_to("download csv", data_url),
-        Err(err) => {
-            warn!(err, "failed to turn interview to data url");
-            ui.label("failed")
-        }
+/// how many sections have more codes than the one row per section format has columns for
+fn crowded_sections(interviews: &[&Interview], roll_up: Option<&[Code]>) -> usize {
+    interviews
+        .iter()
+        .flat_map(|interview| sections(interview, roll_up))
+        .filter(|section| section.codes.len() > CsvSerializableSection::CODE_COLUMNS)
+        .count()
+}
+
+/// the sections of `interview`, with their codes rolled up to the top-level themes of `roll_up`
+fn sections<'a>(
+    interview: &'a Interview,
+    roll_up: Option<&'a [Code]>,
+) -> impl Iterator<Item = Cow<'a, Section>> + 'a {
+    interview.sections.iter().map(move |section| match roll_up {
+        Some(codes) => Cow::Owned(section.rolled_up(codes)),
+        None => Cow::Borrowed(section),
+    })
+}
+
+fn code_matrix_row(
+    codes: &[Code],
+    Interview {
+        name,
+        speakers,
+        memo,
+        ..
+    }: &Interview,
+    index: usize,
+    section: &Section,
+    Columns {
+        include_original,
+        include_memos,
+        include_coders,
+    }: Columns,
+) -> Vec<String> {
+    [
+        name.clone(),
+        index.to_string(),
+        speaker_name(speakers, section.speaker_id),
+        section.start.map(format_timestamp).unwrap_or_default(),
+        section.end.map(format_timestamp).unwrap_or_default(),
+        section.text.clone(),
+    ]
+    .into_iter()
+    .chain(include_original.then(|| {
+        section
+            .original_text
+            .clone()
+            .unwrap_or_else(|| section.text.clone())
+    }))
+    .chain(include_memos.then(|| section.memo.clone()))
+    .chain(include_memos.then(|| memo.clone()))
+    .chain(include_coders.then(|| coded_by(codes, section)))
+    .chain(
+        codes
+            .iter()
+            .map(|Code { id, .. }| if section.has_code(*id) { "1" } else { "0" }.to_string()),
+    )
+    .collect()
+}
+
+pub fn export_codes(codes: &[Code], ui: &mut Ui, report: &mut ExportReport) -> Response {
+    export_rows(
+        ui,
+        "codes.csv",
+        report,
+        codes.iter().map(|code| CsvCode::from_code(codes, code)),
+    )
+}
+
+/// A memo on an interview, one of its sections or a code.
+#[derive(Serialize, Debug, Default)]
+struct CsvMemo {
+    interview: Option<String>,
+    /// the position of the section in the interview
+    section: Option<usize>,
+    speaker: Option<String>,
+    text: Option<String>,
+    code: Option<String>,
+    memo: String,
+}
+
+/// every memo that has been written, interview by interview followed by those on codes
+fn memos<'a>(
+    codes: &'a [Code],
+    interviews: &'a [&'a Interview],
+) -> impl Iterator<Item = CsvMemo> + 'a {
+    let interview_memos = interviews.iter().flat_map(|interview| {
+        let memo = CsvMemo {
+            interview: Some(interview.name.clone()),
+            memo: interview.memo.clone(),
+            ..Default::default()
+        };
+        let section_memos = interview
+            .sections
+            .iter()
+            .enumerate()
+            .map(move |(index, section)| CsvMemo {
+                interview: Some(interview.name.clone()),
+                section: Some(index),
+                speaker: Some(speaker_name(&interview.speakers, section.speaker_id)),
+                text: Some(section.text.clone()),
+                memo: section.memo.clone(),
+                ..Default::default()
+            });
+        std::iter::once(memo).chain(section_memos)
+    });
+    let code_memos = codes.iter().map(|code| CsvMemo {
+        code: Some(code.name.clone()),
+        memo: code.memo.clone(),
+        ..Default::default()
+    });
+    interview_memos
+        .chain(code_memos)
+        .filter(|memo| !memo.memo.is_empty())
+}
+
+pub fn export_memos(
+    codes: &[Code],
+    ui: &mut Ui,
+    interviews: &[&Interview],
+    report: &mut ExportReport,
+) -> Response {
+    export_rows(ui, "memos.csv", report, memos(codes, interviews))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::CodeId;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn code_matrix_has_a_column_per_code() {
+        let codes = vec![
+            Code {
+                id: CodeId(4),
+                name: "revenge".to_string(),
+                description: "".to_string(),
+                ..Default::default()
+            },
+            Code {
+                id: CodeId(2),
+                name: "handedness".to_string(),
+                description: "".to_string(),
+                ..Default::default()
+            },
+        ];
+        let interview = Interview {
+            name: "princess bride".to_string(),
+            speakers: BTreeMap::from([(0, "Inigo Montoya".to_string())]),
+            sections: vec![
+                Section {
+                    speaker_id: 0,
+                    text: "I am not left-handed".to_string(),
+                    codes: [CodeId(2)].into(),
+                    start: Some(1.5),
+                    ..Default::default()
+                },
+                Section {
+                    speaker_id: 0,
+                    text: "Prepare to die".to_string(),
+                    codes: [CodeId(4), CodeId(2)].into(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let csv = to_csv(
+            Vec::new(),
+            code_matrix(
+                &codes,
+                &[&interview],
+                Columns {
+                    include_original: false,
+                    include_memos: false,
+                    include_coders: false,
+                },
+                None,
+            ),
+        )
+        .unwrap()
+        .into_inner()
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "interview,section,speaker,start,end,text,revenge,handedness
+princess bride,0,Inigo Montoya,00:00:01.500,,I am not left-handed,0,1
+princess bride,1,Inigo Montoya,,,Prepare to die,1,1
+"
+        );
+    }
+
+    #[test]
+    fn exported_memos_include_the_interview_memo() {
+        let interview = Interview {
+            name: "princess bride".to_string(),
+            speakers: BTreeMap::from([(0, "Inigo Montoya".to_string())]),
+            sections: vec![Section {
+                speaker_id: 0,
+                text: "Prepare to die".to_string(),
+                memo: "a threat".to_string(),
+                ..Default::default()
+            }],
+            memo: "a duel".to_string(),
+        };
+        let columns = Columns {
+            include_memos: true,
+            ..Default::default()
+        };
+
+        let csv = to_csv(Vec::new(), code_matrix(&[], &[&interview], columns, None))
+            .unwrap()
+            .into_inner()
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "interview,section,speaker,start,end,text,memo,interview_memo
+princess bride,0,Inigo Montoya,,,Prepare to die,a threat,a duel
+"
+        );
+    }
+
+    #[test]
+    fn sections_with_too_many_codes_for_the_wide_format_are_counted() {
+        let codes = (0..10)
+            .map(|id| Code {
+                id: CodeId(id),
+                parent: (id > 0).then(|| CodeId(0)),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        let interview = Interview {
+            sections: vec![
+                Section {
+                    codes: (0..10).map(CodeId).collect(),
+                    ..Default::default()
+                },
+                Section {
+                    codes: (0..9).map(CodeId).collect(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(crowded_sections(&[&interview], None), 1);
+        assert_eq!(crowded_sections(&[&interview], Some(&codes)), 0);
+    }
+
+    #[test]
+    fn memo_export_has_a_row_per_memo() {
+        let codes = vec![
+            Code {
+                id: CodeId(4),
+                name: "revenge".to_string(),
+                memo: "drives the whole plot".to_string(),
+                ..Default::default()
+            },
+            Code {
+                id: CodeId(2),
+                name: "handedness".to_string(),
+                ..Default::default()
+            },
+        ];
+        let interview = Interview {
+            name: "princess bride".to_string(),
+            speakers: BTreeMap::from([(0, "Inigo Montoya".to_string())]),
+            sections: vec![
+                Section {
+                    speaker_id: 0,
+                    text: "I am not left-handed".to_string(),
+                    memo: "a reveal".to_string(),
+                    ..Default::default()
+                },
+                Section {
+                    speaker_id: 0,
+                    text: "Prepare to die".to_string(),
+                    ..Default::default()
+                },
+            ],
+            memo: "a duel".to_string(),
+        };
+
+        let csv = to_csv(Vec::new(), memos(&codes, &[&interview]))
+            .unwrap()
+            .into_inner()
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "interview,section,speaker,text,code,memo
+princess bride,,,,,a duel
+princess bride,0,Inigo Montoya,I am not left-handed,,a reveal
+,,,,revenge,drives the whole plot
+"
+        );
+    }
+
+    #[test]
+    fn export_report_tells_where_the_export_went() {
+        let path = std::env::temp_dir().join("qqc_export_report_test.csv");
+        let mut report = ExportReport::default();
+        assert_eq!(report.message(), None);
+
+        let written = write_to_file(&path, ["a longer first export"].iter())
+            .and_then(|()| write_to_file(&path, ["second"].iter()));
+        report.record(path.clone(), written);
+
+        assert_eq!(
+            report.message(),
+            Some((format!("saved to {}", path.display()), false))
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "second\n");
+        std::fs::remove_file(&path).unwrap();
+
+        report.record(path, Err("disk full".into()));
+
+        assert_eq!(
+            report.message(),
+            Some(("failed to save: disk full".to_string(), true))
+        );
     }
 }
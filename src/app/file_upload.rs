@@ -3,9 +3,26 @@ use std::io::Read;
 use std::path::PathBuf;
 use std::sync::mpsc::Sender;
 
+/// The contents of a file the user picked along with its name.
+#[derive(Debug)]
+pub struct UploadedFile {
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+impl UploadedFile {
+    /// the file name without its extension
+    pub fn stem(&self) -> &str {
+        self.name
+            .rsplit_once('.')
+            .map(|(stem, _)| stem)
+            .unwrap_or(&self.name)
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub fn open_upload_dialog(
-    sender: Sender<Vec<u8>>,
+    sender: Sender<UploadedFile>,
     (name, ext): (&'static str, &'static [&'static str]),
 ) {
     let file = rfd::FileDialog::new().add_filter(name, ext).pick_file();
@@ -14,7 +31,7 @@ pub fn open_upload_dialog(
 
 #[cfg(target_arch = "wasm32")]
 pub fn open_upload_dialog(
-    sender: Sender<Vec<u8>>,
+    sender: Sender<UploadedFile>,
     (name, ext): (&'static str, &'static [&'static str]),
 ) {
     wasm_bindgen_futures::spawn_local(async move {
@@ -23,23 +40,52 @@ pub fn open_upload_dialog(
             .pick_file()
             .await;
         if let Some(file) = file {
-            sender.send(file.read().await).expect("failed to send file")
+            let uploaded = UploadedFile {
+                name: file.file_name(),
+                bytes: file.read().await,
+            };
+            sender.send(uploaded).expect("failed to send file")
         } else {
             tracing::warn!("no file picked!")
         }
     })
 }
 
-fn read_and_send(sender: Sender<Vec<u8>>, file: Option<PathBuf>) {
+fn read_and_send(sender: Sender<UploadedFile>, file: Option<PathBuf>) {
     if let Some(file) = file {
         tracing::trace!(?file, "received file");
+        let name = file
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
         let mut file = File::open(file).expect("could not open passed file");
         tracing::trace!(?file, "successfully opened file");
         let mut buf = Vec::new();
         let bytes_read = file.read_to_end(&mut buf).expect("failed to read file");
         tracing::trace!(bytes_read, "successfully read the file");
-        sender.send(buf).expect("failed to send file")
+        sender
+            .send(UploadedFile { name, bytes: buf })
+            .expect("failed to send file")
     } else {
         tracing::warn!("no file picked!")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str) -> UploadedFile {
+        UploadedFile {
+            name: name.to_string(),
+            bytes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn stem_drops_only_the_last_extension() {
+        assert_eq!(file("interview.vtt").stem(), "interview");
+        assert_eq!(file("interview.en.srt").stem(), "interview.en");
+        assert_eq!(file("interview").stem(), "interview");
+    }
+}
@@ -30,6 +30,63 @@ impl InterviewSwiper {
     }
 }
 
+impl InterviewSwiper {
+    /// Moves to the next section `stop` accepts, by position, if there is one.
+    pub(crate) fn try_next_where(&mut self, stop: impl Fn(usize) -> bool) -> Option<usize> {
+        self.index = (self.index + 1..self.interview.sections.len()).find(|index| stop(*index))?;
+        Some(self.index)
+    }
+
+    /// Moves to the previous section `stop` accepts, by position, if there is one.
+    pub(crate) fn try_prev_where(&mut self, stop: impl Fn(usize) -> bool) -> Option<usize> {
+        self.index = (0..self.index).rev().find(|index| stop(*index))?;
+        Some(self.index)
+    }
+}
+
+/// A change to how the sections around the current one are divided.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Restructure {
+    /// split the current section before the character at this position
+    SplitAt(usize),
+    MergePrevious,
+    MergeNext,
+}
+
+impl InterviewSwiper {
+    /// Splits or merges the current section. The index stays on the current section, which is the
+    /// earlier of the merged sections or the first half of a split one.
+    pub(crate) fn restructure(&mut self, restructure: Restructure) {
+        let sections = &mut self.interview.sections;
+        match restructure {
+            Restructure::SplitAt(at) => {
+                let text = &sections[self.index].text;
+                let at_byte = text
+                    .char_indices()
+                    .nth(at)
+                    .map_or(text.len(), |(index, _)| index);
+                let (first, rest) = text.split_at(at_byte);
+                // splitting off nothing would leave an empty section behind
+                if first.trim().is_empty() || rest.trim().is_empty() {
+                    return;
+                }
+                let rest = sections[self.index].split_off(at);
+                sections.insert(self.index + 1, rest);
+            }
+            Restructure::MergePrevious if self.index > 0 => {
+                let current = sections.remove(self.index);
+                self.index -= 1;
+                sections[self.index].merge(current);
+            }
+            Restructure::MergeNext if self.index + 1 < sections.len() => {
+                let next = sections.remove(self.index + 1);
+                sections[self.index].merge(next);
+            }
+            Restructure::MergePrevious | Restructure::MergeNext => {}
+        }
+    }
+}
+
 impl InterviewSwiper {
     pub fn new(interview: Interview) -> Self {
         if interview.sections.is_empty() {
@@ -80,39 +137,47 @@ mod tests {
     use std::collections::BTreeMap;
 
     use super::*;
+    use crate::app::CodeId;
 
     #[test]
     fn test_window() {
         let mut swiper = InterviewSwiper {
             interview: Interview {
+                name: String::new(),
                 speakers: BTreeMap::default(),
                 sections: vec![
                     Section {
                         speaker_id: 0,
                         text: "0th".to_string(),
                         codes: Default::default(),
+                        ..Default::default()
                     },
                     Section {
                         speaker_id: 0,
                         text: "1st".to_string(),
                         codes: Default::default(),
+                        ..Default::default()
                     },
                     Section {
                         speaker_id: 0,
                         text: "2nd".to_string(),
                         codes: Default::default(),
+                        ..Default::default()
                     },
                     Section {
                         speaker_id: 0,
                         text: "3rd".to_string(),
                         codes: Default::default(),
+                        ..Default::default()
                     },
                     Section {
                         speaker_id: 0,
                         text: "4th".to_string(),
                         codes: Default::default(),
+                        ..Default::default()
                     },
                 ],
+                ..Default::default()
             },
             index: 2,
         };
@@ -129,34 +194,41 @@ mod tests {
     fn test_window_2() {
         let mut swiper = InterviewSwiper {
             interview: Interview {
+                name: String::new(),
                 speakers: BTreeMap::default(),
                 sections: vec![
                     Section {
                         speaker_id: 0,
                         text: "0th".to_string(),
                         codes: Default::default(),
+                        ..Default::default()
                     },
                     Section {
                         speaker_id: 0,
                         text: "1st".to_string(),
                         codes: Default::default(),
+                        ..Default::default()
                     },
                     Section {
                         speaker_id: 0,
                         text: "2nd".to_string(),
                         codes: Default::default(),
+                        ..Default::default()
                     },
                     Section {
                         speaker_id: 0,
                         text: "3rd".to_string(),
                         codes: Default::default(),
+                        ..Default::default()
                     },
                     Section {
                         speaker_id: 0,
                         text: "4th".to_string(),
                         codes: Default::default(),
+                        ..Default::default()
                     },
                 ],
+                ..Default::default()
             },
             index: 0,
         };
@@ -167,40 +239,143 @@ mod tests {
         assert_eq!(after.len(), 1);
         assert_ne!(after[0].text, curr.text);
     }
+
+    fn swiper(sections: &[(u64, &str, &[u64])], index: usize) -> InterviewSwiper {
+        InterviewSwiper {
+            interview: Interview {
+                sections: sections
+                    .iter()
+                    .map(|(speaker_id, text, codes)| Section {
+                        speaker_id: *speaker_id,
+                        text: text.to_string(),
+                        codes: codes.iter().copied().map(CodeId).collect(),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            },
+            index,
+        }
+    }
+
+    #[test]
+    fn split_copies_codes() {
+        let mut swiper = swiper(&[(0, "Hello. Prepare to die", &[1]), (1, "no", &[])], 0);
+
+        swiper.restructure(Restructure::SplitAt(6));
+
+        let sections = &swiper.interview.sections;
+        assert_eq!(swiper.index, 0);
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[0].text, "Hello.");
+        assert_eq!(sections[1].text, "Prepare to die");
+        assert_eq!(sections[1].speaker_id, 0);
+        assert_eq!(sections[1].codes, [CodeId(1)].into());
+        assert_eq!(sections[2].text, "no");
+    }
+
+    #[test]
+    fn split_at_either_end_does_nothing() {
+        for at in [0, 1, 15, 16, 100] {
+            let mut swiper = swiper(&[(0, " Hello. Prepare ", &[1]), (1, "no", &[])], 0);
+
+            swiper.restructure(Restructure::SplitAt(at));
+
+            let sections = &swiper.interview.sections;
+            assert_eq!(swiper.index, 0);
+            assert_eq!(sections.len(), 2, "split at {}", at);
+            assert_eq!(sections[0].text, " Hello. Prepare ");
+        }
+    }
+
+    #[test]
+    fn merge_unions_codes() {
+        let mut swiper = swiper(
+            &[
+                (0, "Hello.", &[1]),
+                (1, "Prepare", &[2]),
+                (1, "to die", &[1, 3]),
+            ],
+            1,
+        );
+
+        swiper.restructure(Restructure::MergeNext);
+        swiper.restructure(Restructure::MergePrevious);
+
+        let sections = &swiper.interview.sections;
+        assert_eq!(swiper.index, 0);
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].text, "Hello. Prepare to die");
+        assert_eq!(sections[0].speaker_id, 0);
+        assert_eq!(sections[0].codes, [CodeId(1), CodeId(2), CodeId(3)].into());
+    }
+
+    #[test]
+    fn stepping_skips_sections_that_are_not_accepted() {
+        let mut swiper = swiper(
+            &[(0, "a", &[1]), (0, "b", &[]), (0, "c", &[]), (0, "d", &[1])],
+            0,
+        );
+        let coded = |index: usize| index == 0 || index == 3;
+
+        assert_eq!(swiper.try_next_where(coded), Some(3));
+        assert_eq!(swiper.try_next_where(coded), None);
+        assert_eq!(swiper.index, 3);
+        assert_eq!(swiper.try_prev_where(coded), Some(0));
+        assert_eq!(swiper.try_prev_where(coded), None);
+    }
+
+    #[test]
+    fn merging_past_the_ends_does_nothing() {
+        let mut swiper = swiper(&[(0, "Hello.", &[])], 0);
+
+        swiper.restructure(Restructure::MergePrevious);
+        swiper.restructure(Restructure::MergeNext);
+
+        assert_eq!(swiper.interview.sections.len(), 1);
+        assert_eq!(swiper.index, 0);
+    }
 }
 
 #[test]
 fn test_window() {
     let mut swiper = InterviewSwiper {
         interview: Interview {
+            name: String::new(),
             speakers: std::collections::BTreeMap::default(),
             sections: vec![
                 Section {
                     speaker_id: 0,
                     text: "0th".to_string(),
                     codes: Default::default(),
+                    ..Default::default()
                 },
                 Section {
                     speaker_id: 0,
                     text: "1st".to_string(),
                     codes: Default::default(),
+                    ..Default::default()
                 },
                 Section {
                     speaker_id: 0,
                     text: "2nd".to_string(),
                     codes: Default::default(),
+                    ..Default::default()
                 },
                 Section {
                     speaker_id: 0,
                     text: "3rd".to_string(),
                     codes: Default::default(),
+                    ..Default::default()
                 },
                 Section {
                     speaker_id: 0,
                     text: "4th".to_string(),
                     codes: Default::default(),
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         },
         index: 4,
     };
@@ -216,12 +391,15 @@ fn test_window() {
 fn test_window_3() {
     let mut swiper = InterviewSwiper {
         interview: Interview {
+            name: String::new(),
             speakers: std::collections::BTreeMap::default(),
             sections: vec![Section {
                 speaker_id: 0,
                 text: "0th".to_string(),
                 codes: Default::default(),
+                ..Default::default()
             }],
+            ..Default::default()
         },
         index: 0,
     };
@@ -0,0 +1,67 @@
+use std::fmt::Display;
+
+use egui::Context;
+use tracing::{error, warn};
+
+/// Problems the user should know about, such as an upload that could not be read, and outcomes
+/// they may not expect, such as coding lost to an edit. They are shown in a window until
+/// dismissed.
+#[derive(Debug, Default)]
+pub struct Notifications(Vec<(String, Severity)>);
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Severity {
+    Warning,
+    Error,
+}
+
+impl Notifications {
+    pub fn error(&mut self, context: &str, err: impl Display) {
+        error!(%err, context);
+        self.0
+            .push((format!("{}: {}", context, err), Severity::Error));
+    }
+
+    pub fn warning(&mut self, context: &str, message: impl Display) {
+        warn!(%message, context);
+        self.0
+            .push((format!("{}: {}", context, message), Severity::Warning));
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        let Notifications(messages) = self;
+        let mut open = !messages.is_empty();
+        let mut dismissed = None;
+        let failed = messages
+            .iter()
+            .any(|(_, severity)| *severity == Severity::Error);
+        let title = if failed {
+            "something went wrong"
+        } else {
+            "please note"
+        };
+        egui::Window::new(title)
+            .id(egui::Id::new("notifications"))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                for (idx, (message, severity)) in messages.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        let color = match severity {
+                            Severity::Warning => ui.visuals().warn_fg_color,
+                            Severity::Error => ui.visuals().error_fg_color,
+                        };
+                        ui.colored_label(color, message);
+                        if ui.small_button("dismiss").clicked() {
+                            dismissed = Some(idx);
+                        }
+                    });
+                }
+            });
+        if let Some(idx) = dismissed {
+            messages.remove(idx);
+        }
+        if !open {
+            messages.clear();
+        }
+    }
+}
@@ -1,36 +1,84 @@
-use crate::app::Interview;
+use crate::app::{Interview, Section};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
 use tracing::warn;
 
+mod segmentation;
+mod srt;
 mod standard;
 mod vtt;
 
+pub use segmentation::ImportOptions;
+
 #[derive(Debug)]
 enum InterviewFormat {
     /// not sure what to call this format for now
     Standard(standard::Root),
     Vtt(vtt::Vtt),
+    Srt(srt::Srt),
 }
 
 pub fn file_extensions() -> &'static [&'static str] {
     &["json", "vtt", "srt"]
 }
 
+/// Why an uploaded file could not be turned into an interview.
+#[derive(Debug)]
+pub enum ParseInterviewError {
+    NotUtf8(std::str::Utf8Error),
+    /// the file looked like json but was not in a format we understand
+    Json(serde_json::Error),
+    Vtt(vtt::VttParseError),
+    Srt(srt::SrtParseError),
+    /// the file was understood but had nothing to code
+    NoSections,
+}
+
+impl Display for ParseInterviewError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseInterviewError::NotUtf8(err) => {
+                write!(f, "the file is not a text file encoded as UTF-8 ({})", err)
+            }
+            ParseInterviewError::Json(err) => {
+                write!(f, "the file is not a transcript we understand: {}", err)
+            }
+            ParseInterviewError::Vtt(err) => write!(f, "the file is not valid WebVTT: {}", err),
+            ParseInterviewError::Srt(err) => write!(f, "the file is not valid SubRip: {}", err),
+            ParseInterviewError::NoSections => write!(f, "the file does not contain any speech"),
+        }
+    }
+}
+
+impl std::error::Error for ParseInterviewError {}
+
 impl TryFrom<&str> for InterviewFormat {
-    type Error = ();
+    type Error = ParseInterviewError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        serde_json::from_str::<standard::Root>(value)
-            .map_err(|err| {
-                warn!(?err, "failed to parse as standard json");
-            })
-            .map(InterviewFormat::Standard)
-            .or_else(|_| {
-                vtt::Vtt::try_from(value)
-                    .map_err(|err| {
-                        warn!(?err, "failed to parse as vtt");
-                    })
-                    .map(InterviewFormat::Vtt)
-            })
+        if value.trim_start().starts_with('{') {
+            serde_json::from_str::<standard::Root>(value)
+                .map(InterviewFormat::Standard)
+                .map_err(|err| {
+                    warn!(?err, "failed to parse as standard json");
+                    ParseInterviewError::Json(err)
+                })
+        } else if srt::is_srt(value) {
+            srt::Srt::try_from(value)
+                .map(InterviewFormat::Srt)
+                .map_err(|err| {
+                    warn!(?err, "failed to parse as srt");
+                    ParseInterviewError::Srt(err)
+                })
+        } else {
+            vtt::Vtt::try_from(value)
+                .map(InterviewFormat::Vtt)
+                .map_err(|err| {
+                    warn!(?err, "failed to parse as vtt");
+                    ParseInterviewError::Vtt(err)
+                })
+        }
     }
 }
 
@@ -39,10 +87,98 @@ impl From<InterviewFormat> for Interview {
         match interview_format {
             InterviewFormat::Standard(standard) => standard.into(),
             InterviewFormat::Vtt(vtt) => vtt.into(),
+            InterviewFormat::Srt(srt) => srt.into(),
+        }
+    }
+}
+
+/// A subtitle along with who said it and when, if we know.
+#[derive(PartialEq, Debug)]
+struct Cue {
+    speaker: Option<String>,
+    text: String,
+    /// in seconds
+    start: Option<f64>,
+    /// in seconds
+    end: Option<f64>,
+}
+
+impl Cue {
+    /// a cue with the speaker taken from a `Speaker: ` prefix of `text`, if there is one
+    fn from_prefixed(text: &str) -> Cue {
+        match text.split_once(": ") {
+            Some((speaker, text)) => Cue {
+                speaker: Some(speaker.to_string()),
+                text: text.to_string(),
+                start: None,
+                end: None,
+            },
+            None => Cue {
+                speaker: None,
+                text: text.to_string(),
+                start: None,
+                end: None,
+            },
         }
     }
 }
 
+/// Turns subtitle cues into an interview, giving every distinct speaker an id. Cues without a
+/// speaker are attributed to an "Unknown" speaker.
+fn interview_from_cues(cues: impl IntoIterator<Item = Cue>) -> Interview {
+    const UNKNOWN_SPEAKER_ID: u64 = 0;
+
+    let mut sections = vec![];
+    let mut speakers = BTreeMap::from([(String::from("Unknown"), UNKNOWN_SPEAKER_ID)]);
+    let mut speaker_id = UNKNOWN_SPEAKER_ID;
+    for Cue {
+        speaker,
+        text,
+        start,
+        end,
+    } in cues
+    {
+        let speaker_id = if let Some(speaker) = speaker {
+            match speakers.entry(speaker) {
+                Entry::Vacant(v) => {
+                    speaker_id += 1;
+                    *v.insert(speaker_id)
+                }
+                Entry::Occupied(o) => *o.get(),
+            }
+        } else {
+            UNKNOWN_SPEAKER_ID
+        };
+        sections.push(Section {
+            speaker_id,
+            text,
+            codes: BTreeSet::new(),
+            start,
+            end,
+            ..Default::default()
+        });
+    }
+    Interview {
+        name: String::new(),
+        speakers: speakers.into_iter().map(|(s, i)| (i, s)).collect(),
+        sections,
+        memo: String::new(),
+    }
+}
+
+pub(crate) fn parse(
+    bytes: &[u8],
+    options: &ImportOptions,
+) -> Result<Interview, ParseInterviewError> {
+    let str = std::str::from_utf8(bytes).map_err(ParseInterviewError::NotUtf8)?;
+    let mut interview = Interview::from(InterviewFormat::try_from(str)?);
+    if interview.sections.is_empty() {
+        return Err(ParseInterviewError::NoSections);
+    }
+    options.apply(&mut interview);
+    Ok(interview)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -80,6 +216,69 @@ mod tests {
         InterviewFormat::try_from(json).unwrap();
     }
 
+    #[test]
+    fn standard_json_sections_span_their_words() {
+        let json = r#"{
+    "speakers": [{ "spkid": "spk1", "name": "Speaker 1" }],
+    "segments": [
+        {
+            "speaker": "spk1",
+            "words": [
+                { "start": 3.06, "end": 3.36, "text": "Okay," },
+                { "start": 3.5, "duration": 0.25, "text": "sure" }
+            ]
+        },
+        { "speaker": "spk1", "words": [{ "text": "untimed" }] }
+    ]
+}"#;
+
+        let interview = parse(json.as_bytes(), &ImportOptions::default()).unwrap();
+
+        assert_eq!(interview.sections[0].start, Some(3.06));
+        assert_eq!(interview.sections[0].end, Some(3.75));
+        assert_eq!(interview.sections[1].start, None);
+        assert_eq!(interview.sections[1].end, None);
+    }
+
+    #[test]
+    fn standard_json_words_keep_their_confidence() {
+        let json = r#"{
+    "speakers": [{ "spkid": "spk1", "name": "Speaker 1" }],
+    "segments": [
+        {
+            "speaker": "spk1",
+            "words": [
+                { "text": "Okay,", "conf": 0.95, "pristine": true },
+                { "text": "shore", "conf": 0.4, "pristine": true },
+                { "text": "fixed", "conf": 0.1, "pristine": false }
+            ]
+        }
+    ]
+}"#;
+
+        let interview = parse(json.as_bytes(), &ImportOptions::default()).unwrap();
+
+        let uncertain = interview.sections[0]
+            .words
+            .iter()
+            .filter(|word| word.is_uncertain(0.8))
+            .map(|word| word.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(uncertain, ["shore"]);
+    }
+
+    #[test]
+    fn subtitle_sections_keep_their_timestamps() {
+        let interview = parse(
+            b"WEBVTT\n\n00:00:01.500 --> 00:00:04.000\nhello",
+            &ImportOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(interview.sections[0].start, Some(1.5));
+        assert_eq!(interview.sections[0].end, Some(4.0));
+    }
+
     #[test]
     fn parse_vtt() {
         let entries = "1
@@ -97,8 +296,41 @@ Marcus Dunn: ewubfqofbweqpfboifjwnpfiwjn pviwljan pilsn pajn wpailfjn owefba ou
 
         InterviewFormat::try_from(entries).unwrap();
     }
-}
 
-pub(crate) fn parse(str: &str) -> Result<Interview, ()> {
-    InterviewFormat::try_from(str).map(Interview::from)
+    #[test]
+    fn parse_srt() {
+        let entries = "1
+00:00:09,640 --> 00:00:13,459
+Marcus Dunn: ewubfqofbweqpfboifjwnpfiwjn
+
+2
+00:00:13,470 --> 00:00:43,370
+Edward Cunningham: ewubfqofbweqpfboifjwnpfiwjn
+";
+
+        assert!(matches!(
+            InterviewFormat::try_from(entries),
+            Ok(InterviewFormat::Srt(_))
+        ));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(matches!(
+            parse(&[0xff, 0xfe, 0x00], &ImportOptions::default()),
+            Err(ParseInterviewError::NotUtf8(_))
+        ));
+        assert!(matches!(
+            parse(br#"{ "speakers": [] }"#, &ImportOptions::default()),
+            Err(ParseInterviewError::Json(_))
+        ));
+        assert!(matches!(
+            parse(b"WEBVTT\n\n1\n", &ImportOptions::default()),
+            Err(ParseInterviewError::Vtt(vtt::VttParseError { line: 3, .. }))
+        ));
+        assert!(matches!(
+            parse(b"WEBVTT\n", &ImportOptions::default()),
+            Err(ParseInterviewError::NoSections)
+        ));
+    }
 }
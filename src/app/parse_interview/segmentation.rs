@@ -0,0 +1,186 @@
+//! Re-dividing an imported interview into sections that are easier to code, such as when a
+//! transcription service cuts every few seconds rather than at every change of speaker.
+
+use crate::app::{Interview, Section};
+
+/// How to re-divide an interview into sections on import.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct ImportOptions {
+    /// merge consecutive sections spoken by the same speaker
+    pub merge_same_speaker: bool,
+    /// split sections longer than `max_section_length` characters between sentences
+    pub split_long_sections: bool,
+    pub max_section_length: usize,
+    /// give every sentence its own section
+    pub split_sentences: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            merge_same_speaker: false,
+            split_long_sections: false,
+            max_section_length: 500,
+            split_sentences: false,
+        }
+    }
+}
+
+impl ImportOptions {
+    /// Merges, then splits, the sections of `interview`.
+    pub(super) fn apply(&self, interview: &mut Interview) {
+        if self.merge_same_speaker {
+            interview.sections = merge_same_speaker(std::mem::take(&mut interview.sections));
+        }
+        let max_length = if self.split_sentences {
+            Some(0)
+        } else if self.split_long_sections {
+            Some(self.max_section_length)
+        } else {
+            None
+        };
+        if let Some(max_length) = max_length {
+            interview.sections = std::mem::take(&mut interview.sections)
+                .into_iter()
+                .flat_map(|section| split_long_section(section, max_length))
+                .collect();
+        }
+    }
+}
+
+fn merge_same_speaker(sections: Vec<Section>) -> Vec<Section> {
+    let mut merged: Vec<Section> = Vec::with_capacity(sections.len());
+    for section in sections {
+        match merged.last_mut() {
+            Some(last) if last.speaker_id == section.speaker_id => last.merge(section),
+            _ => merged.push(section),
+        }
+    }
+    merged
+}
+
+/// Splits a section between sentences so that each part is at most `max_length` characters long
+/// where possible. A sentence longer than `max_length` is left whole.
+fn split_long_section(mut section: Section, max_length: usize) -> Vec<Section> {
+    section.text = section.text.trim().to_string();
+    let length = section.text.chars().count();
+    let mut cuts = Vec::new();
+    let mut start = 0;
+    let mut previous = None;
+    for boundary in sentence_starts(&section.text).chain(std::iter::once(length)) {
+        if boundary - start > max_length {
+            if let Some(cut) = previous.filter(|cut| *cut > start) {
+                cuts.push(cut);
+                start = cut;
+            }
+        }
+        previous = Some(boundary);
+    }
+    // splitting from the end keeps the positions of the earlier cuts valid
+    let mut sections = cuts
+        .into_iter()
+        .rev()
+        .map(|cut| section.split_off(cut))
+        .collect::<Vec<_>>();
+    sections.push(section);
+    sections.reverse();
+    sections
+}
+
+/// The character positions at which every sentence but the first starts. A sentence ends with `.`,
+/// `!` or `?`, along with any closing quotes or brackets, followed by whitespace.
+fn sentence_starts(text: &str) -> impl Iterator<Item = usize> + '_ {
+    let chars = text.chars().collect::<Vec<_>>();
+    (1..chars.len()).filter(move |&index| {
+        if chars[index].is_whitespace() || !chars[index - 1].is_whitespace() {
+            return false;
+        }
+        chars[..index]
+            .iter()
+            .rev()
+            .skip_while(|char| char.is_whitespace())
+            .find(|char| !matches!(char, '"' | '\'' | ')' | ']' | '”' | '’'))
+            .map_or(false, |char| matches!(char, '.' | '!' | '?'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interview(sections: &[(u64, &str)]) -> Interview {
+        Interview {
+            sections: sections
+                .iter()
+                .map(|(speaker_id, text)| Section {
+                    speaker_id: *speaker_id,
+                    text: text.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn texts(interview: &Interview) -> Vec<(u64, &str)> {
+        interview
+            .sections
+            .iter()
+            .map(|section| (section.speaker_id, section.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn merges_consecutive_sections_of_a_speaker() {
+        let mut interview = interview(&[(1, "Hello."), (1, "My name is"), (2, "Hi"), (1, "Bye")]);
+
+        ImportOptions {
+            merge_same_speaker: true,
+            ..Default::default()
+        }
+        .apply(&mut interview);
+
+        assert_eq!(
+            texts(&interview),
+            [(1, "Hello. My name is"), (2, "Hi"), (1, "Bye")]
+        );
+    }
+
+    #[test]
+    fn splits_into_sentences() {
+        let mut interview = interview(&[(1, "Hello. My name is \"Inigo!\" Prepare to die? ")]);
+
+        ImportOptions {
+            split_sentences: true,
+            ..Default::default()
+        }
+        .apply(&mut interview);
+
+        assert_eq!(
+            texts(&interview),
+            [
+                (1, "Hello."),
+                (1, "My name is \"Inigo!\""),
+                (1, "Prepare to die?")
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_long_sections_between_sentences() {
+        let mut interview = interview(&[(1, "One. Two. Three is long. Four.")]);
+
+        ImportOptions {
+            split_long_sections: true,
+            max_section_length: 10,
+            ..Default::default()
+        }
+        .apply(&mut interview);
+
+        assert_eq!(
+            texts(&interview),
+            [(1, "One. Two."), (1, "Three is long."), (1, "Four.")]
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! A parser for SubRip (.srt) files.
+
+use crate::app::parse_interview::vtt::{blocks, parse_timing};
+use crate::app::parse_interview::{interview_from_cues, Cue};
+use crate::app::Interview;
+use std::fmt::{Display, Formatter};
+use std::num::ParseIntError;
+
+#[derive(PartialEq, Debug)]
+pub(crate) struct Srt(Vec<SrtEntry>);
+
+impl From<Srt> for Interview {
+    /// Subtitles left without text, such as those that only held formatting, are skipped.
+    fn from(Srt(srt): Srt) -> Self {
+        interview_from_cues(
+            srt.into_iter()
+                .map(|SrtEntry { cue, .. }| cue)
+                .filter(|cue| !cue.text.is_empty()),
+        )
+    }
+}
+
+/// Whether the file looks like SubRip rather than WebVTT, which is the case when the first timing
+/// line separates milliseconds with a comma.
+pub(super) fn is_srt(value: &str) -> bool {
+    value
+        .lines()
+        .find(|line| line.contains("-->"))
+        .and_then(|line| line.split_once("-->"))
+        .map_or(false, |(start, _)| start.contains(','))
+}
+
+impl TryFrom<&str> for Srt {
+    type Error = SrtParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.strip_prefix('\u{feff}').unwrap_or(value);
+        blocks(value)
+            .map(|(line, block)| {
+                SrtEntry::try_from(block.as_slice()).map_err(|error| SrtParseError { line, error })
+            })
+            .collect()
+    }
+}
+
+impl FromIterator<SrtEntry> for Srt {
+    fn from_iter<T: IntoIterator<Item = SrtEntry>>(iter: T) -> Self {
+        Srt(Vec::from_iter(iter))
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct SrtEntry {
+    /// the counter before the timestamps, which some tools leave out
+    index: Option<usize>,
+    cue: Cue,
+}
+
+impl TryFrom<&[&str]> for SrtEntry {
+    type Error = SrtEntryParseError;
+
+    fn try_from(block: &[&str]) -> Result<Self, Self::Error> {
+        let (index, timing, text) = match block {
+            [timing, text @ ..] if timing.contains("-->") => (None, *timing, text),
+            [index, timing, text @ ..] if timing.contains("-->") => {
+                (Some(index.trim().parse::<usize>()?), *timing, text)
+            }
+            _ => return Err(SrtEntryParseError::MissingTimestamps(block.join("\n"))),
+        };
+        let (start, end) = parse_timing(timing, ',')
+            .ok_or_else(|| SrtEntryParseError::InvalidTimestamp(timing.to_string()))?;
+        let text = text
+            .iter()
+            .map(|line| strip_formatting(line))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        Ok(SrtEntry {
+            index,
+            cue: Cue {
+                start: Some(start),
+                end: Some(end),
+                ..Cue::from_prefixed(&text)
+            },
+        })
+    }
+}
+
+/// Removes html-like tags such as `<i>` and `<font color="red">` along with `{\an8}` style tags.
+/// A `<` or `{` that does not start such a tag, as in "x < y", is kept.
+fn strip_formatting(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find(['<', '{']) {
+        stripped.push_str(&rest[..start]);
+        let after = &rest[start..];
+        match tag_length(after) {
+            Some(length) => rest = &after[length..],
+            None => {
+                let open = after.chars().next().map_or(1, char::len_utf8);
+                stripped.push_str(&after[..open]);
+                rest = &after[open..];
+            }
+        }
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// the length of the tag `text` starts with, if it starts with one: `<` followed by a letter or
+/// `/` up to the next `>`, or `{\` up to the next `}`
+fn tag_length(text: &str) -> Option<usize> {
+    let (close, looks_like_tag) = match text.strip_prefix('<') {
+        Some(inner) => (
+            '>',
+            inner.starts_with(|c: char| c.is_ascii_alphabetic() || c == '/'),
+        ),
+        None => ('}', text.starts_with("{\\")),
+    };
+    let end = text.find(close)?;
+    let nested = text[1..end].contains(['<', '{']);
+    (looks_like_tag && !nested).then(|| end + 1)
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum SrtEntryParseError {
+    MissingTimestamps(String),
+    InvalidTimestamp(String),
+    IndexParseError(ParseIntError),
+}
+
+impl From<ParseIntError> for SrtEntryParseError {
+    fn from(parse_int_error: ParseIntError) -> Self {
+        SrtEntryParseError::IndexParseError(parse_int_error)
+    }
+}
+
+impl Display for SrtEntryParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SrtEntryParseError::MissingTimestamps(_) => write!(f, "has no timestamps"),
+            SrtEntryParseError::InvalidTimestamp(line) => {
+                write!(f, "has invalid timestamps \"{}\"", line)
+            }
+            SrtEntryParseError::IndexParseError(err) => {
+                write!(f, "does not start with a numeric index ({})", err)
+            }
+        }
+    }
+}
+
+/// A subtitle that could not be parsed along with the line it starts on.
+#[derive(Debug, Eq, PartialEq)]
+pub struct SrtParseError {
+    pub line: usize,
+    pub error: SrtEntryParseError,
+}
+
+impl Display for SrtParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the subtitle on line {} {}", self.line, self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        index: Option<usize>,
+        (start, end): (f64, f64),
+        speaker: Option<&str>,
+        text: &str,
+    ) -> SrtEntry {
+        SrtEntry {
+            index,
+            cue: Cue {
+                speaker: speaker.map(String::from),
+                text: text.to_string(),
+                start: Some(start),
+                end: Some(end),
+            },
+        }
+    }
+
+    #[test]
+    fn test_parse_srt() {
+        let entries = "1
+00:00:01,000 --> 00:00:04,000
+Inigo Montoya: Hello. My name is Inigo Montoya.
+You killed my father.
+
+2
+00:00:05,000 --> 00:00:07,500 X1:40 X2:600 Y1:20 Y2:50
+<i>Prepare to die.</i>
+
+3
+00:00:08,000 --> 00:00:09,000
+{\\an8}Man in Black: Stop saying that!
+";
+
+        assert_eq!(
+            entries.try_into(),
+            Ok(Srt(vec![
+                entry(
+                    Some(1),
+                    (1.0, 4.0),
+                    Some("Inigo Montoya"),
+                    "Hello. My name is Inigo Montoya. You killed my father."
+                ),
+                entry(Some(2), (5.0, 7.5), None, "Prepare to die."),
+                entry(
+                    Some(3),
+                    (8.0, 9.0),
+                    Some("Man in Black"),
+                    "Stop saying that!"
+                ),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_literal_brackets_are_kept() {
+        assert_eq!(strip_formatting("x < y and {z}"), "x < y and {z}");
+        assert_eq!(strip_formatting("a <b>bold</b> {\\an8}move"), "a bold move");
+        assert_eq!(strip_formatting("<3 you <i>truly"), "<3 you truly");
+    }
+
+    #[test]
+    fn test_subtitles_without_text_are_skipped() {
+        let entries = "1
+00:00:01,000 --> 00:00:02,000
+<i></i>
+
+2
+00:00:02,000 --> 00:00:03,000
+As you wish.
+";
+
+        let interview = Interview::from(Srt::try_from(entries).unwrap());
+
+        assert_eq!(interview.sections.len(), 1);
+        assert_eq!(interview.sections[0].text, "As you wish.");
+    }
+
+    #[test]
+    fn test_first_subtitle_is_kept() {
+        let entries = "\u{feff}1\r\n00:00:01,000 --> 00:00:02,000\r\nfirst\r\n\r\n2\r\n00:00:02,000 --> 00:00:03,000\r\nsecond\r\n";
+
+        assert_eq!(
+            entries.try_into(),
+            Ok(Srt(vec![
+                entry(Some(1), (1.0, 2.0), None, "first"),
+                entry(Some(2), (2.0, 3.0), None, "second"),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parse_error_has_line() {
+        let entries = "1
+00:00:01,000 --> 00:00:02,000
+first
+
+two
+00:00:02,000 --> 00:00:03,000
+second
+";
+
+        assert!(matches!(
+            Srt::try_from(entries),
+            Err(SrtParseError {
+                line: 5,
+                error: SrtEntryParseError::IndexParseError(_)
+            })
+        ));
+    }
+
+    #[test]
+    fn test_is_srt() {
+        assert!(is_srt("1\n00:00:01,000 --> 00:00:02,000\nhi"));
+        assert!(!is_srt("WEBVTT\n\n00:00:01.000 --> 00:00:02.000\nhi"));
+    }
+}
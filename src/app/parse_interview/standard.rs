@@ -2,7 +2,7 @@ use std::collections::hash_map::DefaultHasher;
 use std::collections::BTreeSet;
 use std::hash::{Hash, Hasher};
 
-use crate::app::{Interview, Section};
+use crate::app::{Interview, Section, Word as TranscribedWord};
 
 #[derive(serde::Deserialize, Debug)]
 pub struct Root {
@@ -27,11 +27,26 @@ struct Segment {
 #[derive(serde::Deserialize, Debug)]
 struct Word {
     text: String,
+    /// in seconds
+    start: Option<f64>,
+    /// in seconds
+    end: Option<f64>,
+    /// in seconds, for transcripts that give a duration rather than an end
+    duration: Option<f64>,
+    conf: Option<f64>,
+    pristine: Option<bool>,
+}
+
+impl Word {
+    fn end(&self) -> Option<f64> {
+        self.end.or_else(|| Some(self.start? + self.duration?))
+    }
 }
 
 impl From<Root> for Interview {
     fn from(root: Root) -> Self {
         Interview {
+            name: String::new(),
             speakers: root
                 .speakers
                 .into_iter()
@@ -50,10 +65,32 @@ impl From<Root> for Interview {
                         speaker_id.hash(&mut hasher);
                         hasher.finish()
                     },
-                    text: words.into_iter().map(|Word { text }| text + " ").collect(),
+                    start: words.first().and_then(|word| word.start),
+                    end: words.last().and_then(Word::end),
+                    text: words
+                        .iter()
+                        .map(|Word { text, .. }| text.clone() + " ")
+                        .collect(),
+                    words: words
+                        .into_iter()
+                        .map(
+                            |Word {
+                                 text,
+                                 conf,
+                                 pristine,
+                                 ..
+                             }| TranscribedWord {
+                                text,
+                                confidence: conf,
+                                pristine,
+                            },
+                        )
+                        .collect(),
                     codes: BTreeSet::default(),
+                    ..Default::default()
                 })
                 .collect(),
+            memo: String::new(),
         }
     }
 }
@@ -1,71 +1,235 @@
-use crate::app::{Interview, Section};
-use std::collections::btree_map::Entry;
-use std::collections::{BTreeMap, BTreeSet};
-use std::num::ParseIntError;
+//! A parser for [WebVTT](https://www.w3.org/TR/webvtt1/) files.
+//!
+//! Comment, style and region blocks are skipped, cue identifiers and settings are optional and cue
+//! text can span several lines. Speakers are taken from voice spans (`<v Speaker>`) or, failing
+//! that, a `Speaker: ` prefix.
+
+use crate::app::parse_interview::{interview_from_cues, Cue};
+use crate::app::Interview;
+use std::fmt::{Display, Formatter};
 use std::ops::Deref;
 use tracing::info;
 
-#[derive(Eq, PartialEq, Debug)]
+#[derive(PartialEq, Debug)]
 pub(crate) struct Vtt(Vec<VttEntry>);
 
 impl From<Vtt> for Interview {
     fn from(Vtt(vtt): Vtt) -> Self {
-        const UNKNOWN_SPEAKER_ID: u64 = 0;
+        interview_from_cues(vtt.into_iter().map(
+            |VttEntry {
+                 speaker,
+                 text,
+                 start,
+                 end,
+                 ..
+             }| Cue {
+                speaker,
+                text,
+                start: Some(start),
+                end: Some(end),
+            },
+        ))
+    }
+}
+
+impl TryFrom<&str> for Vtt {
+    type Error = VttParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.strip_prefix('\u{feff}').unwrap_or(value);
+        let mut blocks = blocks(value).peekable();
+        // the header is required by the spec but we accept files without one
+        if let Some((_, header)) = blocks.peek() {
+            if is_block_of_kind(header[0], "WEBVTT") {
+                blocks.next();
+            }
+        }
+        let mut entries = Vec::new();
+        for (line, block) in blocks {
+            if ["NOTE", "STYLE", "REGION"]
+                .iter()
+                .any(|kind| is_block_of_kind(block[0], kind))
+            {
+                info!(line, "skipping block");
+                continue;
+            }
+            let cue = parse_cue(&block).map_err(|error| VttParseError { line, error })?;
+            entries.extend(cue);
+        }
+        Ok(Vtt(entries))
+    }
+}
 
-        let mut sections = vec![];
-        let mut speakers = BTreeMap::from([(String::from("Unknown"), UNKNOWN_SPEAKER_ID)]);
-        let mut speaker_id = UNKNOWN_SPEAKER_ID;
-        for VttEntry {
-            index: _,
+/// Splits the file into blocks of consecutive non-empty lines along with the (1 based) line each
+/// block starts on.
+pub(super) fn blocks(value: &str) -> impl Iterator<Item = (usize, Vec<&str>)> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(usize, Vec<&str>)> = None;
+    for (idx, line) in value.lines().enumerate() {
+        if line.trim().is_empty() {
+            blocks.extend(current.take());
+        } else {
+            current
+                .get_or_insert_with(|| (idx + 1, Vec::new()))
+                .1
+                .push(line);
+        }
+    }
+    blocks.extend(current);
+    blocks.into_iter()
+}
+
+/// whether `line` is `keyword` on its own or followed by whitespace
+fn is_block_of_kind(line: &str, keyword: &str) -> bool {
+    line.strip_prefix(keyword).map_or(false, |rest| {
+        rest.is_empty() || rest.starts_with([' ', '\t'])
+    })
+}
+
+/// Parses a cue block, returning an entry for every speaker in it.
+fn parse_cue(block: &[&str]) -> Result<Vec<VttEntry>, VttEntryParseError> {
+    let (identifier, timing, payload) = match block {
+        [timing, payload @ ..] if timing.contains("-->") => (None, *timing, payload),
+        [identifier, timing, payload @ ..] if timing.contains("-->") => {
+            (Some(identifier.to_string()), *timing, payload)
+        }
+        _ => return Err(VttEntryParseError::MissingTimestamps(block.join("\n"))),
+    };
+    let (start, end) = parse_timing(timing, '.')
+        .ok_or_else(|| VttEntryParseError::InvalidTimestamp(timing.to_string()))?;
+    Ok(parse_payload(&payload.join("\n"))
+        .into_iter()
+        .map(|(speaker, text)| VttEntry {
+            identifier: identifier.clone(),
             speaker,
             text,
-        } in vtt
-        {
-            let speaker_id = if let Some(speaker) = speaker {
-                match speakers.entry(speaker) {
-                    Entry::Vacant(v) => {
-                        speaker_id += 1;
-                        *v.insert(speaker_id)
-                    }
-                    Entry::Occupied(o) => *o.get(),
+            start,
+            end,
+        })
+        .collect())
+}
+
+/// Parses `start --> end` ignoring any cue settings or coordinates after the end, returning both
+/// in seconds. `separator` comes before the milliseconds, `.` in WebVTT and `,` in SubRip.
+pub(super) fn parse_timing(line: &str, separator: char) -> Option<(f64, f64)> {
+    let (start, rest) = line.split_once("-->")?;
+    let end = rest.split_whitespace().next()?;
+    Some((
+        parse_timestamp(start.trim(), separator)?,
+        parse_timestamp(end, separator)?,
+    ))
+}
+
+/// Parses `hh:mm:ss.ttt` or `mm:ss.ttt`, with `separator` in place of the `.`, into seconds.
+fn parse_timestamp(timestamp: &str, separator: char) -> Option<f64> {
+    let (rest, millis) = timestamp.split_once(separator)?;
+    let mut parts = rest.rsplit(':');
+    let seconds = parts.next()?;
+    let minutes = parts.next()?;
+    let hours = parts.next().unwrap_or("0");
+    if parts.next().is_some() || millis.len() != 3 || seconds.len() != 2 || minutes.len() != 2 {
+        return None;
+    }
+    let number = |part: &str| {
+        if part.bytes().all(|b| b.is_ascii_digit()) {
+            part.parse::<u64>().ok()
+        } else {
+            None
+        }
+    };
+    let (hours, minutes, seconds, millis) = (
+        number(hours)?,
+        number(minutes)?,
+        number(seconds)?,
+        number(millis)?,
+    );
+    if minutes > 59 || seconds > 59 {
+        return None;
+    }
+    Some((hours * 3600 + minutes * 60 + seconds) as f64 + millis as f64 / 1000.0)
+}
+
+/// Splits cue text into runs of text by the same speaker, removing markup. Text outside of a voice
+/// span is attributed to the speaker named in a `Speaker: ` prefix, if any.
+fn parse_payload(payload: &str) -> Vec<(Option<String>, String)> {
+    let mut runs: Vec<(Option<String>, String)> = vec![(None, String::new())];
+    let mut rest = payload;
+    while !rest.is_empty() {
+        match rest.find('<') {
+            Some(0) => {
+                let (tag, after) = match rest.find('>') {
+                    Some(end) => (&rest[1..end], &rest[end + 1..]),
+                    None => (&rest[1..], ""),
+                };
+                if let Some(voice) = voice_of(tag) {
+                    runs.push((Some(voice), String::new()));
+                } else if tag == "/v" {
+                    runs.push((None, String::new()));
                 }
-            } else {
-                UNKNOWN_SPEAKER_ID
-            };
-            sections.push(Section {
-                speaker_id,
-                text,
-                codes: BTreeSet::new(),
-            });
+                rest = after;
+            }
+            Some(start) => {
+                push_text(&mut runs, &rest[..start]);
+                rest = &rest[start..];
+            }
+            None => {
+                push_text(&mut runs, rest);
+                rest = "";
+            }
+        }
+    }
+    let mut merged: Vec<(Option<String>, String)> = Vec::new();
+    for (speaker, text) in runs {
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            continue;
         }
-        Interview {
-            speakers: speakers.into_iter().map(|(s, i)| (i, s)).collect(),
-            sections,
+        let (speaker, text) = match speaker {
+            Some(speaker) => (Some(speaker), text),
+            None => {
+                let Cue { speaker, text, .. } = Cue::from_prefixed(&text);
+                (speaker, text)
+            }
+        };
+        match merged.last_mut() {
+            Some((last_speaker, last_text)) if *last_speaker == speaker => {
+                last_text.push(' ');
+                last_text.push_str(&text);
+            }
+            _ => merged.push((speaker, text)),
         }
     }
+    merged
 }
 
-impl TryFrom<&str> for Vtt {
-    type Error = VttEntryParseError;
+/// the annotation of a voice start tag such as `v.loud Esme`
+fn voice_of(tag: &str) -> Option<String> {
+    let tag = tag.strip_prefix('v')?;
+    if !(tag.starts_with('.') || tag.starts_with([' ', '\t'])) {
+        return None;
+    }
+    let (_classes, annotation) = tag.split_once([' ', '\t'])?;
+    let annotation = decode_entities(annotation.trim());
+    (!annotation.is_empty()).then(|| annotation)
+}
 
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let normalized = value.replace("\r\n", "\n");
-        let split = normalized
-            .split("\n\n")
-            .filter(|s| !s.is_empty())
-            .collect::<Vec<_>>();
-        info!(?split);
-        let skip = split.into_iter().skip(1).collect::<Vec<_>>();
-        info!(?skip);
-        let map = skip
-            .into_iter()
-            .map(VttEntry::try_from)
-            .collect::<Result<Vtt, _>>();
-        info!(?map);
-        map
+fn push_text(runs: &mut [(Option<String>, String)], text: &str) {
+    if let Some((_, run)) = runs.last_mut() {
+        run.push_str(&decode_entities(text));
     }
 }
 
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&lrm;", "\u{200e}")
+        .replace("&rlm;", "\u{200f}")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
 impl FromIterator<VttEntry> for Vtt {
     fn from_iter<T: IntoIterator<Item = VttEntry>>(iter: T) -> Self {
         Vtt(Vec::from_iter(iter))
@@ -80,52 +244,45 @@ impl Deref for Vtt {
     }
 }
 
-#[derive(Eq, PartialEq, Debug)]
+/// A cue or, if a cue has several speakers, the part of a cue spoken by one of them.
+#[derive(PartialEq, Debug)]
 pub struct VttEntry {
-    index: usize,
+    identifier: Option<String>,
     speaker: Option<String>,
     text: String,
+    /// in seconds
+    start: f64,
+    /// in seconds
+    end: f64,
 }
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum VttEntryParseError {
-    MissingIndex(String),
     MissingTimestamps(String),
-    MissingText(String),
-    IndexParseError(ParseIntError),
+    InvalidTimestamp(String),
 }
 
-impl From<ParseIntError> for VttEntryParseError {
-    fn from(parse_int_error: ParseIntError) -> Self {
-        VttEntryParseError::IndexParseError(parse_int_error)
-    }
+/// A cue that could not be parsed along with the line it starts on.
+#[derive(Debug, Eq, PartialEq)]
+pub struct VttParseError {
+    pub line: usize,
+    pub error: VttEntryParseError,
 }
 
-impl TryFrom<&str> for VttEntry {
-    type Error = VttEntryParseError;
+impl Display for VttParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the cue on line {} {}", self.line, self.error)
+    }
+}
 
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let mut lines = value.lines();
-        let index = lines
-            .next()
-            .ok_or_else(|| VttEntryParseError::MissingIndex(value.to_string()))?
-            .parse::<usize>()?;
-        let _timestamps = lines
-            .next()
-            .ok_or_else(|| VttEntryParseError::MissingTimestamps(value.to_string()))?;
-        let text = lines
-            .next()
-            .ok_or_else(|| VttEntryParseError::MissingText(value.to_string()))?;
-        let (speaker, text) = match text.split_once(": ") {
-            None => (None, text),
-            Some((speaker, text)) => (Some(speaker), text),
-        };
-        debug_assert!(lines.next().is_none(), "entry contained more than 3 lines");
-        Ok(VttEntry {
-            index,
-            speaker: speaker.map(String::from),
-            text: text.to_string(),
-        })
+impl Display for VttEntryParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VttEntryParseError::MissingTimestamps(_) => write!(f, "has no timestamps"),
+            VttEntryParseError::InvalidTimestamp(line) => {
+                write!(f, "has invalid timestamps \"{}\"", line)
+            }
+        }
     }
 }
 
@@ -133,18 +290,59 @@ impl TryFrom<&str> for VttEntry {
 mod tests {
     use super::*;
 
+    fn entry(
+        identifier: Option<&str>,
+        (start, end): (f64, f64),
+        speaker: Option<&str>,
+        text: &str,
+    ) -> VttEntry {
+        VttEntry {
+            identifier: identifier.map(String::from),
+            speaker: speaker.map(String::from),
+            text: text.to_string(),
+            start,
+            end,
+        }
+    }
+
     #[test]
     fn test_parse_single_vtt_entry() {
-        let entry = "1
+        let entry_block = [
+            "1",
+            "00:00:09.640 --> 00:00:13.459",
+            "Marcus Dunn: Yo yo yo yo, this is a test",
+        ];
+
+        assert_eq!(
+            parse_cue(&entry_block),
+            Ok(vec![entry(
+                Some("1"),
+                (9.64, 13.459),
+                Some("Marcus Dunn"),
+                "Yo yo yo yo, this is a test"
+            )])
+        );
+    }
+
+    #[test]
+    fn test_parse_error_has_line() {
+        let entries = "WEBVTT
+
+1
 00:00:09.640 --> 00:00:13.459
-Marcus Dunn: Yo yo yo yo, this is a test";
+Marcus Dunn: hello
 
+2
+00:00:13,470 --> 00:00:43.370
+Edward Cunningham: hi
+";
         assert_eq!(
-            entry.try_into(),
-            Ok(VttEntry {
-                index: 1,
-                speaker: Some("Marcus Dunn".to_string()),
-                text: "Yo yo yo yo, this is a test".to_string(),
+            Vtt::try_from(entries),
+            Err(VttParseError {
+                line: 7,
+                error: VttEntryParseError::InvalidTimestamp(
+                    "00:00:13,470 --> 00:00:43.370".to_string()
+                ),
             })
         );
     }
@@ -168,25 +366,95 @@ ewubfqofbweqpfboifjwnpfiwjn pviwljan pilsn pajn wpailfjn owefba ou
         assert_eq!(
             entries.try_into(),
             Ok(Vtt(vec![
-                VttEntry {
-                    index: 1,
-                    speaker: Some("Marcus Dunn".to_string()),
-                    text: "ewubfqofbweqpfboifjwnpfiwjn pviwljan pilsn pajn wpailfjn aps"
-                        .to_string(),
-                },
-                VttEntry {
-                    index: 2,
-                    speaker: Some("Edward Cunningham".to_string()),
-                    text: "ewubfqofbweqpfboifjwnpfiwjn pviwljan pilsn pajn wpailfjn aonwe fi"
-                        .to_string(),
-                },
-                VttEntry {
-                    index: 3,
-                    speaker: None,
-                    text: "ewubfqofbweqpfboifjwnpfiwjn pviwljan pilsn pajn wpailfjn owefba ou"
-                        .to_string(),
-                },
+                entry(
+                    Some("1"),
+                    (9.64, 13.459),
+                    Some("Marcus Dunn"),
+                    "ewubfqofbweqpfboifjwnpfiwjn pviwljan pilsn pajn wpailfjn aps"
+                ),
+                entry(
+                    Some("2"),
+                    (13.47, 43.37),
+                    Some("Edward Cunningham"),
+                    "ewubfqofbweqpfboifjwnpfiwjn pviwljan pilsn pajn wpailfjn aonwe fi"
+                ),
+                entry(
+                    Some("3"),
+                    (43.38, 50.87),
+                    None,
+                    "ewubfqofbweqpfboifjwnpfiwjn pviwljan pilsn pajn wpailfjn owefba ou"
+                ),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parse_full_grammar() {
+        let entries = "\u{feff}WEBVTT - an interview
+Kind: captions
+
+STYLE
+::cue(v[voice=\"Esme\"]) { color: cyan }
+
+NOTE this is a comment
+that spans lines
+
+REGION
+id:fred width:40%
+
+intro
+00:01.000 --> 00:04.000 position:10%,line-left align:left size:35%
+<v.loud Esme>Never drink liquid nitrogen.</v>
+
+00:00:05.000 --> 00:00:09.000
+<v Mary>It will perforate
+your &amp; stomach.
+<v Esme>You could <i>die</i>.
+
+00:00:10.000 --> 00:00:11.000
+Mary: That's the only way
+";
+        assert_eq!(
+            entries.try_into(),
+            Ok(Vtt(vec![
+                entry(
+                    Some("intro"),
+                    (1.0, 4.0),
+                    Some("Esme"),
+                    "Never drink liquid nitrogen."
+                ),
+                entry(
+                    None,
+                    (5.0, 9.0),
+                    Some("Mary"),
+                    "It will perforate your & stomach."
+                ),
+                entry(None, (5.0, 9.0), Some("Esme"), "You could die."),
+                entry(None, (10.0, 11.0), Some("Mary"), "That's the only way"),
             ]))
         );
     }
+
+    #[test]
+    fn test_parse_without_header() {
+        let entries =
+            "00:00:01.000 --> 00:00:02.000\nhello\n\n00:00:02.000 --> 00:00:03.000\nworld";
+
+        assert_eq!(
+            entries.try_into(),
+            Ok(Vtt(vec![
+                entry(None, (1.0, 2.0), None, "hello"),
+                entry(None, (2.0, 3.0), None, "world")
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("01:02:03.004", '.'), Some(3723.004));
+        assert_eq!(parse_timestamp("02:03.500", '.'), Some(123.5));
+        assert_eq!(parse_timestamp("02:03,500", '.'), None);
+        assert_eq!(parse_timestamp("02:03,500", ','), Some(123.5));
+        assert_eq!(parse_timestamp("02:60.500", '.'), None);
+    }
 }
@@ -0,0 +1,452 @@
+use std::fmt::{Display, Formatter};
+
+use crate::app::interview::InterviewSwiper;
+use crate::app::{
+    deserialize_codes, deserialize_interviews, Code, CodeId, QualityQualitativeCoding,
+};
+
+/// The version of the project file format written by this version of qqc. Bump it whenever the
+/// format changes in a way older versions cannot read. Version 2 held several interviews and
+/// version 3 added the id of the next code along with the coded excerpts, coders, memos and
+/// reviews of sections, which version 2 readers would drop.
+const VERSION: u32 = 3;
+
+/// The file extension of project files.
+pub const EXTENSION: &str = "qqc";
+
+/// Everything needed to carry on coding somewhere else, as written to a project file.
+#[derive(serde::Serialize, Debug)]
+pub struct ProjectRef<'a> {
+    version: u32,
+    pub interviews: &'a [InterviewSwiper],
+    pub current_interview: usize,
+    pub codes: &'a [Code],
+    pub next_code_id: CodeId,
+}
+
+impl<'a> ProjectRef<'a> {
+    pub fn new(
+        interviews: &'a [InterviewSwiper],
+        current_interview: usize,
+        codes: &'a [Code],
+        next_code_id: CodeId,
+    ) -> Self {
+        Self {
+            version: VERSION,
+            interviews,
+            current_interview,
+            codes,
+            next_code_id,
+        }
+    }
+}
+
+/// A project read from a project file.
+#[derive(serde::Deserialize, Debug)]
+pub struct Project {
+    version: u32,
+    /// the interviews along with the section the coder was on in each. Version 1 files have a
+    /// single optional `interview`.
+    #[serde(alias = "interview", deserialize_with = "deserialize_interviews")]
+    pub interviews: Vec<InterviewSwiper>,
+    #[serde(default)]
+    pub current_interview: usize,
+    #[serde(deserialize_with = "deserialize_codes")]
+    pub codes: Vec<Code>,
+    /// the id the next new code gets, see [`CodeId::next`]
+    #[serde(default)]
+    pub next_code_id: CodeId,
+}
+
+#[derive(Debug)]
+pub enum ProjectError {
+    Json(serde_json::Error),
+    /// the file was written by a newer version of qqc
+    UnsupportedVersion(u32),
+    /// the interview with this name has no sections to code
+    EmptyInterview(String),
+    /// the coder was on a section past the end of the interview with this name
+    InvalidSection {
+        interview: String,
+        index: usize,
+        sections: usize,
+    },
+    /// the coder was on an interview past the end of the project
+    InvalidInterview {
+        index: usize,
+        interviews: usize,
+    },
+    /// a section of the interview with this name is spoken by a speaker the interview doesn't have
+    UnknownSpeaker {
+        interview: String,
+        section: usize,
+        speaker: u64,
+    },
+}
+
+impl Display for ProjectError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProjectError::Json(err) => write!(f, "not a valid project file: {}", err),
+            ProjectError::UnsupportedVersion(version) => write!(
+                f,
+                "the project file has version {} but this version of qqc only supports up to {}",
+                version, VERSION
+            ),
+            ProjectError::EmptyInterview(name) => {
+                write!(f, "the interview \"{}\" has no sections", name)
+            }
+            ProjectError::InvalidSection {
+                interview,
+                index,
+                sections,
+            } => write!(
+                f,
+                "the project is on section {} of \"{}\" but it only has {} sections",
+                index + 1,
+                interview,
+                sections
+            ),
+            ProjectError::InvalidInterview { index, interviews } => write!(
+                f,
+                "the project is on interview {} but it only has {} interviews",
+                index + 1,
+                interviews
+            ),
+            ProjectError::UnknownSpeaker {
+                interview,
+                section,
+                speaker,
+            } => write!(
+                f,
+                "section {} of \"{}\" is spoken by speaker {}, who is not one of its speakers",
+                section + 1,
+                interview,
+                speaker
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProjectError {}
+
+impl From<serde_json::Error> for ProjectError {
+    fn from(err: serde_json::Error) -> Self {
+        ProjectError::Json(err)
+    }
+}
+
+pub fn to_json(project: &ProjectRef<'_>) -> Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(project)
+}
+
+pub fn from_json(json: &str) -> Result<Project, ProjectError> {
+    let project = serde_json::from_str::<Project>(json)?;
+    if project.version > VERSION {
+        return Err(ProjectError::UnsupportedVersion(project.version));
+    }
+    validate(&project.interviews, project.current_interview)?;
+    let sections = project
+        .interviews
+        .iter()
+        .flat_map(|swiper| &swiper.interview.sections);
+    let issued = project.next_code_id.max(CodeId::after_sections(sections));
+    Ok(Project {
+        next_code_id: CodeId::next(&project.codes, issued),
+        ..project
+    })
+}
+
+/// Reads a project file or, if it has no version, the state qqc keeps between sessions saved as
+/// JSON, which has everything a project file has but the version.
+pub fn from_project_or_state(json: &str) -> Result<Project, ProjectError> {
+    #[derive(serde::Deserialize)]
+    struct Versioned {
+        version: Option<u32>,
+    }
+    if serde_json::from_str::<Versioned>(json)?.version.is_some() {
+        return from_json(json);
+    }
+    let state = serde_json::from_str::<QualityQualitativeCoding>(json)?.restored();
+    validate(&state.interviews, state.current_interview)?;
+    Ok(Project {
+        version: VERSION,
+        next_code_id: CodeId::next(&state.codes, state.next_code_id),
+        interviews: state.interviews,
+        current_interview: state.current_interview,
+        codes: state.codes,
+    })
+}
+
+/// Checks that the coder is on an interview and section that exist and that every section has a
+/// speaker, so a hand edited or truncated file can't be opened in a state the app can't show.
+pub fn validate(
+    interviews: &[InterviewSwiper],
+    current_interview: usize,
+) -> Result<(), ProjectError> {
+    for InterviewSwiper { interview, index } in interviews {
+        if interview.sections.is_empty() {
+            return Err(ProjectError::EmptyInterview(interview.name.clone()));
+        }
+        if *index >= interview.sections.len() {
+            return Err(ProjectError::InvalidSection {
+                interview: interview.name.clone(),
+                index: *index,
+                sections: interview.sections.len(),
+            });
+        }
+        let unknown = interview
+            .sections
+            .iter()
+            .position(|section| !interview.speakers.contains_key(&section.speaker_id));
+        if let Some(section) = unknown {
+            return Err(ProjectError::UnknownSpeaker {
+                interview: interview.name.clone(),
+                section,
+                speaker: interview.sections[section].speaker_id,
+            });
+        }
+    }
+    if current_interview > 0 && current_interview >= interviews.len() {
+        return Err(ProjectError::InvalidInterview {
+            index: current_interview,
+            interviews: interviews.len(),
+        });
+    }
+    Ok(())
+}
+
+/// the name to suggest when saving a project for the first time
+pub fn suggested_file_name(interviews: &[InterviewSwiper]) -> String {
+    match interviews {
+        [InterviewSwiper { interview, .. }] if !interview.name.is_empty() => {
+            format!("{}.{}", interview.name, EXTENSION)
+        }
+        _ => format!("project.{}", EXTENSION),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn to_data_url(project: &ProjectRef<'_>) -> Result<String, serde_json::Error> {
+    Ok(String::from("data:application/json,") + &urlencoding::encode(&to_json(project)?))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn pick_save_path(interviews: &[InterviewSwiper]) -> Option<std::path::PathBuf> {
+    rfd::FileDialog::new()
+        .add_filter("qqc project", &[EXTENSION])
+        .set_file_name(&suggested_file_name(interviews))
+        .save_file()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn pick_open_path() -> Option<std::path::PathBuf> {
+    rfd::FileDialog::new()
+        .add_filter("qqc project", &[EXTENSION])
+        .pick_file()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(
+    path: &std::path::Path,
+    project: &ProjectRef<'_>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(std::fs::write(path, to_json(project)?)?)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn open(path: &std::path::Path) -> Result<Project, Box<dyn std::error::Error>> {
+    Ok(from_json(&std::fs::read_to_string(path)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{Interview, Section};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn round_trip() {
+        let interviews = vec![InterviewSwiper {
+            interview: Interview {
+                name: "princess bride".to_string(),
+                speakers: BTreeMap::from([(7, "Inigo Montoya".to_string())]),
+                sections: vec![
+                    Section {
+                        speaker_id: 7,
+                        text: "Hello".to_string(),
+                        ..Default::default()
+                    },
+                    Section {
+                        speaker_id: 7,
+                        text: "Prepare to die".to_string(),
+                        codes: [CodeId(3)].into(),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            },
+            index: 1,
+        }];
+        let codes = vec![Code {
+            id: CodeId(3),
+            name: "revenge".to_string(),
+            description: "".to_string(),
+            ..Default::default()
+        }];
+
+        let project =
+            from_json(&to_json(&ProjectRef::new(&interviews, 0, &codes, CodeId(5))).unwrap())
+                .unwrap();
+
+        let [InterviewSwiper { interview, index }]: [InterviewSwiper; 1] =
+            project.interviews.try_into().unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(interview.speakers[&7], "Inigo Montoya");
+        assert_eq!(interview.sections[1].codes, [CodeId(3)].into());
+        assert_eq!(project.codes, codes);
+        assert_eq!(project.next_code_id, CodeId(5));
+    }
+
+    #[test]
+    fn version_1_files_are_migrated() {
+        let json = r#"{
+    "version": 1,
+    "interview": {
+        "interview": {
+            "name": "princess bride",
+            "speakers": { "0": "Inigo Montoya" },
+            "sections": [{ "speaker_id": 0, "text": "Hello", "codes": [], "start": null, "end": null }]
+        },
+        "index": 0
+    },
+    "codes": []
+}"#;
+
+        let project = from_json(json).unwrap();
+
+        assert_eq!(project.interviews.len(), 1);
+        assert_eq!(project.interviews[0].interview.name, "princess bride");
+        assert_eq!(project.current_interview, 0);
+    }
+
+    #[test]
+    fn version_2_files_are_migrated() {
+        let json = r#"{
+    "version": 2,
+    "interviews": [{
+        "interview": {
+            "name": "princess bride",
+            "speakers": { "0": "Inigo Montoya" },
+            "sections": [{ "speaker_id": 0, "text": "Hello", "codes": [0] }]
+        },
+        "index": 0
+    }],
+    "codes": [{ "name": "greeting", "description": "" }]
+}"#;
+
+        let project = from_json(json).unwrap();
+
+        let section = &project.interviews[0].interview.sections[0];
+        assert_eq!(section.codes, [CodeId(0)].into());
+        assert!(section.spans.is_empty());
+        assert!(section.applications.is_empty());
+        assert_eq!(project.codes[0].id, CodeId(0));
+        assert_eq!(project.next_code_id, CodeId(1));
+        let saved = to_json(&ProjectRef::new(
+            &project.interviews,
+            0,
+            &project.codes,
+            project.next_code_id,
+        ))
+        .unwrap();
+        assert!(saved.contains(r#""version": 3"#));
+    }
+
+    #[test]
+    fn positions_past_the_end_are_rejected() {
+        let project = |sections: &str, index: usize, current_interview: usize| {
+            format!(
+                r#"{{ "version": 2, "current_interview": {}, "codes": [], "interviews": [{{
+                    "interview": {{
+                        "name": "princess bride",
+                        "speakers": {{ "0": "Inigo Montoya" }},
+                        "sections": [{}]
+                    }},
+                    "index": {}
+                }}] }}"#,
+                current_interview, sections, index
+            )
+        };
+        let section = r#"{ "speaker_id": 0, "text": "Hello", "codes": [] }"#;
+
+        assert!(from_json(&project(section, 0, 0)).is_ok());
+        assert!(matches!(
+            from_json(&project("", 0, 0)),
+            Err(ProjectError::EmptyInterview(_))
+        ));
+        assert!(matches!(
+            from_json(&project(section, 1, 0)),
+            Err(ProjectError::InvalidSection { index: 1, .. })
+        ));
+        assert!(matches!(
+            from_json(&project(section, 0, 1)),
+            Err(ProjectError::InvalidInterview { index: 1, .. })
+        ));
+        let unknown_speaker = r#"{ "speaker_id": 3, "text": "Hello", "codes": [] }"#;
+        assert!(matches!(
+            from_json(&project(&format!("{}, {}", section, unknown_speaker), 0, 0)),
+            Err(ProjectError::UnknownSpeaker {
+                section: 1,
+                speaker: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn state_is_read_as_a_project() {
+        let state = r#"{
+    "settings": { "coder": "Inigo" },
+    "interviews": [{
+        "interview": {
+            "name": "princess bride",
+            "speakers": { "0": "Inigo Montoya" },
+            "sections": [{ "speaker_id": 0, "text": "Hello", "codes": [4] }]
+        },
+        "index": 0
+    }],
+    "codes": [{ "id": 1, "name": "greeting", "description": "" }],
+    "merge_open": true
+}"#;
+
+        let project = from_project_or_state(state).unwrap();
+
+        assert_eq!(project.interviews[0].interview.name, "princess bride");
+        assert_eq!(project.codes[0].name, "greeting");
+        assert_eq!(project.next_code_id, CodeId(5));
+        let project = r#"{ "version": 1000, "interviews": [], "codes": [] }"#;
+        assert!(matches!(
+            from_project_or_state(project),
+            Err(ProjectError::UnsupportedVersion(1000))
+        ));
+        assert!(matches!(
+            from_project_or_state(
+                r#"{ "interviews": [{ "interview": {
+                "name": "empty", "speakers": {}, "sections": []
+            }, "index": 0 }] }"#
+            ),
+            Err(ProjectError::EmptyInterview(_))
+        ));
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        let json = r#"{ "version": 1000, "interviews": [], "codes": [] }"#;
+
+        assert!(matches!(
+            from_json(json),
+            Err(ProjectError::UnsupportedVersion(1000))
+        ));
+    }
+}
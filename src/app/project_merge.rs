@@ -0,0 +1,825 @@
+//! Combining the project files or saved state of several coders, who coded the same transcripts
+//! against the same codebook, into one project. Codes are matched by name and everything the files disagree on is
+//! reported rather than silently dropped.
+
+use crate::app::file_upload::{self, UploadedFile};
+use crate::app::interview::InterviewSwiper;
+use crate::app::notifications::Notifications;
+use crate::app::project::{self, Project};
+use crate::app::{Code, CodeApplication, CodeId, CodedSpan, Interview, Section};
+use egui::Ui;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+
+/// Something the merged files disagree on. The merge keeps what the files before `file` had.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Conflict {
+    /// a code of the same name has a different description, theme, color or codebook entry
+    Code {
+        code: String,
+        file: String,
+        /// what differs, as labelled in the codebook
+        fields: Vec<&'static str>,
+    },
+    /// the interview was split into sections differently, so its coding could not be lined up
+    Sections {
+        interview: String,
+        file: String,
+        kept: usize,
+        other: usize,
+    },
+    /// a speaker, or with a section the speaker of that section, has a different name
+    Speaker {
+        interview: String,
+        section: Option<usize>,
+        file: String,
+        kept: String,
+        other: String,
+    },
+    /// both files corrected the transcript of a section, differently
+    Text {
+        interview: String,
+        section: usize,
+        file: String,
+        kept: String,
+        other: String,
+    },
+    /// the same coder, or with `None` no recorded coder, applied different codes to a section.
+    /// The merge has the codes of both.
+    Codes {
+        interview: String,
+        section: usize,
+        file: String,
+        coder: Option<String>,
+        kept: Vec<String>,
+        other: Vec<String>,
+    },
+    /// coded excerpts whose text is not in the merged text of the section, which were left out
+    Excerpts {
+        interview: String,
+        section: usize,
+        file: String,
+        dropped: usize,
+    },
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Conflict::Code { code, file, fields } => write!(
+                f,
+                "code \"{}\": {} has a different {}, which was left out",
+                code,
+                file,
+                fields.join(", ")
+            ),
+            Conflict::Sections {
+                interview,
+                file,
+                kept,
+                other,
+            } => write!(
+                f,
+                "{}: {} has {} sections rather than {}, its coding of the interview was left out",
+                interview, file, other, kept
+            ),
+            Conflict::Speaker {
+                interview,
+                section: None,
+                file,
+                kept,
+                other,
+            } => write!(
+                f,
+                "{}: {} calls speaker \"{}\" \"{}\"",
+                interview, file, kept, other
+            ),
+            Conflict::Speaker {
+                interview,
+                section: Some(section),
+                file,
+                kept,
+                other,
+            } => write!(
+                f,
+                "{}, section {}: {} has \"{}\" speaking rather than \"{}\"",
+                interview,
+                section + 1,
+                file,
+                other,
+                kept
+            ),
+            Conflict::Text {
+                interview,
+                section,
+                file,
+                kept,
+                other,
+            } => write!(
+                f,
+                "{}, section {}: {} corrected the text to \"{}\" rather than \"{}\"",
+                interview,
+                section + 1,
+                file,
+                other,
+                kept
+            ),
+            Conflict::Codes {
+                interview,
+                section,
+                file,
+                coder,
+                kept,
+                other,
+            } => {
+                write!(
+                    f,
+                    "{}, section {}: {} has {} as {} rather than {}",
+                    interview,
+                    section + 1,
+                    file,
+                    coder.as_ref().map_or(
+                        "the codes without a coder".to_string(),
+                        |coder| format!("{}'s codes", coder)
+                    ),
+                    names(other),
+                    names(kept)
+                )
+            }
+            Conflict::Excerpts {
+                interview,
+                section,
+                file,
+                dropped,
+            } => write!(
+                f,
+                "{}, section {}: {} coded excerpt(s) are not in the text once {} is merged and \
+                were left out",
+                interview,
+                section + 1,
+                dropped,
+                file
+            ),
+        }
+    }
+}
+
+fn names(names: &[String]) -> String {
+    if names.is_empty() {
+        "none".to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
+/// The project made from several files along with what they disagreed on.
+#[derive(Debug, Default)]
+pub struct Merged {
+    pub interviews: Vec<InterviewSwiper>,
+    pub codes: Vec<Code>,
+    /// the id the next new code gets, past the ids any of the files have given out
+    pub next_code_id: CodeId,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Merges the projects into the first of them, in order.
+pub fn merge(projects: &[(String, Project)]) -> Merged {
+    let mut merged = Merged {
+        next_code_id: projects
+            .iter()
+            .map(|(_, project)| project.next_code_id)
+            .max()
+            .unwrap_or_default(),
+        ..Merged::default()
+    };
+    for (file, project) in projects {
+        let ids = merge_codes(
+            &mut merged.codes,
+            &project.codes,
+            merged.next_code_id,
+            file,
+            &mut merged.conflicts,
+        );
+        for InterviewSwiper { interview, index } in &project.interviews {
+            let existing = merged
+                .interviews
+                .iter_mut()
+                .find(|swiper| swiper.interview.name == interview.name);
+            match existing {
+                Some(existing) => merge_interview(
+                    &mut existing.interview,
+                    interview,
+                    file,
+                    &ids,
+                    &merged.codes,
+                    &mut merged.conflicts,
+                ),
+                None => merged.interviews.push(InterviewSwiper {
+                    interview: with_ids(interview, &ids),
+                    index: *index,
+                }),
+            }
+        }
+    }
+    merged.next_code_id = CodeId::next(&merged.codes, merged.next_code_id);
+    merged
+}
+
+/// Adds the codes whose names are not yet in `merged`, returning the id each of `codes` has in
+/// `merged`.
+fn merge_codes(
+    merged: &mut Vec<Code>,
+    codes: &[Code],
+    issued: CodeId,
+    file: &str,
+    conflicts: &mut Vec<Conflict>,
+) -> BTreeMap<CodeId, CodeId> {
+    let mut ids = BTreeMap::new();
+    let mut parents = Vec::new();
+    for code in codes {
+        let id = match merged.iter().find(|existing| existing.name == code.name) {
+            Some(existing) => {
+                let fields = differing_fields((existing, merged), (code, codes));
+                if !fields.is_empty() {
+                    conflicts.push(Conflict::Code {
+                        code: code.name.clone(),
+                        file: file.to_string(),
+                        fields,
+                    });
+                }
+                existing.id
+            }
+            None => {
+                let id = CodeId::next(merged, issued);
+                if let Some(parent) = Code::parent_of(codes, code.id) {
+                    parents.push((
+                        merged.len(),
+                        Code::name_of(codes, parent).unwrap_or_default().to_string(),
+                    ));
+                }
+                merged.push(Code {
+                    id,
+                    parent: None,
+                    ..code.clone()
+                });
+                id
+            }
+        };
+        ids.insert(code.id, id);
+    }
+    Code::set_parents_by_name(merged, parents);
+    ids
+}
+
+/// what differs between two codes of the same name, each along with the codebook it is from
+fn differing_fields(
+    (kept, kept_codes): (&Code, &[Code]),
+    (other, other_codes): (&Code, &[Code]),
+) -> Vec<&'static str> {
+    let parent = |code: &Code, codes| {
+        Code::parent_of(codes, code.id).and_then(|parent| Code::name_of(codes, parent))
+    };
+    [
+        ("description", kept.description != other.description),
+        (
+            "part of",
+            parent(kept, kept_codes) != parent(other, other_codes),
+        ),
+        ("color", kept.color != other.color),
+        ("definition", kept.definition != other.definition),
+        ("when to use", kept.inclusion != other.inclusion),
+        ("when not to use", kept.exclusion != other.exclusion),
+        ("example", kept.example != other.example),
+    ]
+    .into_iter()
+    .filter(|(_, differs)| *differs)
+    .map(|(field, _)| field)
+    .collect()
+}
+
+/// the interview with its codes given the ids they have in the merged codebook
+fn with_ids(interview: &Interview, ids: &BTreeMap<CodeId, CodeId>) -> Interview {
+    let id = |code: &CodeId| ids.get(code).copied();
+    Interview {
+        sections: interview
+            .sections
+            .iter()
+            .map(|section| Section {
+                codes: section.codes.iter().filter_map(id).collect(),
+                spans: section
+                    .spans
+                    .iter()
+                    .filter_map(|span| {
+                        Some(CodedSpan {
+                            code: id(&span.code)?,
+                            ..*span
+                        })
+                    })
+                    .collect(),
+                applications: section
+                    .applications
+                    .iter()
+                    .filter_map(|application| {
+                        Some(CodeApplication {
+                            code: id(&application.code)?,
+                            ..application.clone()
+                        })
+                    })
+                    .collect(),
+                ..section.clone()
+            })
+            .collect(),
+        ..interview.clone()
+    }
+}
+
+fn merge_interview(
+    merged: &mut Interview,
+    interview: &Interview,
+    file: &str,
+    ids: &BTreeMap<CodeId, CodeId>,
+    codes: &[Code],
+    conflicts: &mut Vec<Conflict>,
+) {
+    let interview = with_ids(interview, ids);
+    let name = merged.name.clone();
+    if merged.sections.len() != interview.sections.len() {
+        conflicts.push(Conflict::Sections {
+            interview: name,
+            file: file.to_string(),
+            kept: merged.sections.len(),
+            other: interview.sections.len(),
+        });
+        return;
+    }
+    for (id, speaker) in &interview.speakers {
+        match merged.speakers.get(id) {
+            Some(kept) if kept != speaker => conflicts.push(Conflict::Speaker {
+                interview: name.clone(),
+                section: None,
+                file: file.to_string(),
+                kept: kept.clone(),
+                other: speaker.clone(),
+            }),
+            Some(_) => {}
+            None => {
+                merged.speakers.insert(*id, speaker.clone());
+            }
+        }
+    }
+    append_memo(&mut merged.memo, &interview.memo);
+    let coders = |interview: &Interview| {
+        interview
+            .sections
+            .iter()
+            .flat_map(|section| &section.applications)
+            .map(|application| application.coder.clone())
+            .collect::<BTreeSet<_>>()
+    };
+    // only coders who coded the interview in both files can disagree with themselves
+    let shared_coders = coders(merged)
+        .intersection(&coders(&interview))
+        .cloned()
+        .map(Some)
+        .chain(std::iter::once(None))
+        .collect::<Vec<_>>();
+    for (index, (kept, other)) in merged
+        .sections
+        .iter_mut()
+        .zip(interview.sections)
+        .enumerate()
+    {
+        if kept.speaker_id != other.speaker_id {
+            let speaker = |id| merged.speakers.get(&id).cloned().unwrap_or_default();
+            conflicts.push(Conflict::Speaker {
+                interview: name.clone(),
+                section: Some(index),
+                file: file.to_string(),
+                kept: speaker(kept.speaker_id),
+                other: speaker(other.speaker_id),
+            });
+        }
+        let mut dropped = match merge_text(kept, &other) {
+            Some(dropped) => dropped,
+            None => {
+                conflicts.push(Conflict::Text {
+                    interview: name.clone(),
+                    section: index,
+                    file: file.to_string(),
+                    kept: kept.text.clone(),
+                    other: other.text.clone(),
+                });
+                0
+            }
+        };
+        for coder in &shared_coders {
+            let [kept_codes, other_codes] =
+                [&*kept, &other].map(|section| codes_of(section, coder.as_deref(), codes));
+            if kept_codes != other_codes {
+                conflicts.push(Conflict::Codes {
+                    interview: name.clone(),
+                    section: index,
+                    file: file.to_string(),
+                    coder: coder.clone(),
+                    kept: kept_codes,
+                    other: other_codes,
+                });
+            }
+        }
+        dropped += merge_codings(kept, other);
+        if dropped > 0 {
+            conflicts.push(Conflict::Excerpts {
+                interview: name.clone(),
+                section: index,
+                file: file.to_string(),
+                dropped,
+            });
+        }
+    }
+}
+
+/// Takes the text of `other` if only it was corrected, returning how many of the coded excerpts
+/// of `kept` are no longer in its text, or `None` if both were corrected differently.
+fn merge_text(kept: &mut Section, other: &Section) -> Option<usize> {
+    let transcript = |section: &Section| {
+        section
+            .original_text
+            .clone()
+            .unwrap_or_else(|| section.text.clone())
+    };
+    if kept.text == other.text || other.text == transcript(kept) {
+        Some(0)
+    } else if kept.text == transcript(other) {
+        let dropped = kept.replace_text(other.text.clone());
+        kept.original_text = other.original_text.clone();
+        kept.words = other.words.clone();
+        Some(dropped)
+    } else {
+        None
+    }
+}
+
+/// the names of the codes `coder`, or with `None` no recorded coder, applied to the section
+fn codes_of(section: &Section, coder: Option<&str>, codes: &[Code]) -> Vec<String> {
+    section
+        .codes
+        .iter()
+        .filter(|id| match coder {
+            Some(_) => section.coded_by(**id, coder),
+            None => section.coders_of(**id).is_empty(),
+        })
+        .filter_map(|id| Code::name_of(codes, *id))
+        .map(String::from)
+        .collect()
+}
+
+/// Adds the codes, coded excerpts, memo and review of `other` to `kept`, returning how many of the
+/// excerpts are not in the text of `kept`.
+fn merge_codings(kept: &mut Section, mut other: Section) -> usize {
+    // the excerpts of `other` are positions in its own text
+    let dropped = other.replace_text(kept.text.clone());
+    kept.codes.extend(other.codes);
+    kept.spans.extend(other.spans);
+    for application in other.applications {
+        if !kept.coded_by(application.code, Some(&application.coder)) {
+            kept.applications.push(application);
+        }
+    }
+    append_memo(&mut kept.memo, &other.memo);
+    kept.review = kept.review.max(other.review);
+    dropped
+}
+
+fn append_memo(memo: &mut String, other: &str) {
+    if memo.is_empty() {
+        *memo = other.to_string();
+    } else if !other.is_empty() && !memo.contains(other) {
+        *memo = format!("{}\n\n{}", memo, other);
+    }
+}
+
+/// The project files picked for merging.
+#[derive(Debug)]
+pub struct ProjectMerge {
+    files: Vec<(String, Project)>,
+    merged: Option<Merged>,
+    channel: (Sender<UploadedFile>, Receiver<UploadedFile>),
+}
+
+impl Default for ProjectMerge {
+    fn default() -> Self {
+        Self {
+            files: Vec::new(),
+            merged: None,
+            channel: channel(),
+        }
+    }
+}
+
+impl ProjectMerge {
+    /// Reads any project or state files the user has added.
+    pub fn try_update(&mut self, notifications: &mut Notifications) {
+        match self.channel.1.try_recv() {
+            Ok(UploadedFile { name, bytes }) => {
+                match project::from_project_or_state(&String::from_utf8_lossy(&bytes)) {
+                    Ok(project) => {
+                        self.files.push((name, project));
+                        self.merged = None;
+                    }
+                    Err(err) => notifications.error(&format!("could not read {}", name), err),
+                }
+            }
+            Err(TryRecvError::Empty) => { /* no file has been uploaded yet - no problem! */ }
+            Err(TryRecvError::Disconnected) => {
+                panic!("impossible to upload files. sender has been dropped.")
+            }
+        }
+    }
+
+    /// Shows the files being merged and what they disagree on, returning the merged project if
+    /// the user wants to open it.
+    pub fn ui(&mut self, ui: &mut Ui) -> Option<Merged> {
+        let mut removed = None;
+        for (index, (name, _)) in self.files.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(name);
+                if ui.button("remove").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+        if let Some(removed) = removed {
+            self.files.remove(removed);
+            self.merged = None;
+        }
+        if ui
+            .button("add project file")
+            .on_hover_text(
+                "a project coded against the same transcripts and codebook, or the state qqc keeps \
+                between sessions saved as JSON",
+            )
+            .clicked()
+        {
+            file_upload::open_upload_dialog(
+                self.channel.0.clone(),
+                ("qqc project", &[project::EXTENSION, "json"]),
+            );
+        }
+        if self.files.len() < 2 {
+            ui.label("add the project files of two or more coders to merge them");
+            return None;
+        }
+        let merged = self.merged.get_or_insert_with(|| merge(&self.files));
+        if merged.conflicts.is_empty() {
+            ui.label("the files do not conflict");
+        } else {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("{} conflicts", merged.conflicts.len()),
+            );
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    for conflict in &merged.conflicts {
+                        ui.label(conflict.to_string());
+                    }
+                });
+        }
+        // the position in each interview comes from one of the files, check it against the merge
+        let openable = match project::validate(&merged.interviews, 0) {
+            Ok(()) if merged.interviews.is_empty() => {
+                ui.label("the files have no interviews to merge");
+                false
+            }
+            Ok(()) => true,
+            Err(err) => {
+                ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                false
+            }
+        };
+        let open = ui
+            .add_enabled(openable, egui::Button::new("open merged project"))
+            .on_hover_text("replaces the project you have open, save it first to keep it")
+            .clicked();
+        if open {
+            self.files.clear();
+            self.merged.take()
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::CodeColor;
+
+    fn project(codes: &[(u64, &str)], sections: Vec<Section>) -> Project {
+        let json = serde_json::json!({
+            "version": 2,
+            "interviews": [{
+                "interview": Interview {
+                    name: "interview".to_string(),
+                    speakers: [(1, "Inigo".to_string())].into(),
+                    sections,
+                    ..Default::default()
+                },
+                "index": 0,
+            }],
+            "codes": codes
+                .iter()
+                .map(|(id, name)| Code {
+                    id: CodeId(*id),
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>(),
+        });
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn section(text: &str, codes: &[(u64, &str)]) -> Section {
+        let mut section = Section {
+            speaker_id: 1,
+            text: text.to_string(),
+            ..Default::default()
+        };
+        for (code, coder) in codes {
+            section.apply(CodeId(*code), coder, 0);
+        }
+        section
+    }
+
+    #[test]
+    fn codes_are_matched_by_name() {
+        let first = project(&[(0, "anger")], vec![section("hello", &[(0, "alice")])]);
+        let second = project(
+            &[(0, "calm"), (1, "anger")],
+            vec![section("hello", &[(0, "bob"), (1, "bob")])],
+        );
+
+        let merged = merge(&[("a".to_string(), first), ("b".to_string(), second)]);
+
+        let names = merged.codes.iter().map(|code| code.name.as_str());
+        assert_eq!(names.collect::<Vec<_>>(), ["anger", "calm"]);
+        let section = &merged.interviews[0].interview.sections[0];
+        assert_eq!(section.codes, [CodeId(0), CodeId(1)].into());
+        assert_eq!(section.coders_of(CodeId(0)), ["alice", "bob"]);
+        assert_eq!(merged.conflicts, []);
+    }
+
+    #[test]
+    fn codes_with_different_details_are_reported() {
+        let mut first = project(
+            &[(0, "anger"), (1, "feelings")],
+            vec![section("hello", &[])],
+        );
+        first.codes[0].parent = Some(CodeId(1));
+        let mut second = project(
+            &[(0, "feelings"), (1, "anger")],
+            vec![section("hello", &[])],
+        );
+        second.codes[1].parent = Some(CodeId(0));
+        second.codes[1].description = "seeing red".to_string();
+        second.codes[1].example = "You killed my father".to_string();
+
+        let merged = merge(&[("a".to_string(), first), ("b".to_string(), second)]);
+
+        assert_eq!(
+            merged.conflicts,
+            [Conflict::Code {
+                code: "anger".to_string(),
+                file: "b".to_string(),
+                fields: vec!["description", "example"],
+            }]
+        );
+        assert_eq!(merged.codes[0].description, "");
+        let mut third = project(
+            &[(0, "anger"), (1, "feelings")],
+            vec![section("hello", &[])],
+        );
+        third.codes[0].color = Some(CodeColor([255, 0, 0]));
+        let mut first = project(
+            &[(0, "anger"), (1, "feelings")],
+            vec![section("hello", &[])],
+        );
+        first.codes[0].parent = Some(CodeId(1));
+
+        let merged = merge(&[("a".to_string(), first), ("c".to_string(), third)]);
+
+        assert_eq!(
+            merged.conflicts,
+            [Conflict::Code {
+                code: "anger".to_string(),
+                file: "c".to_string(),
+                fields: vec!["part of", "color"],
+            }]
+        );
+    }
+
+    #[test]
+    fn disagreements_are_reported() {
+        let mut corrected = section("hello there", &[(0, "alice")]);
+        corrected.original_text = Some("hello".to_string());
+        let first = project(&[(0, "anger")], vec![corrected, section("bye", &[])]);
+        let mut corrected = section("hello you", &[]);
+        corrected.original_text = Some("hello".to_string());
+        let mut second = project(&[(0, "anger")], vec![corrected, section("bye", &[])]);
+        second.interviews[0].interview.speakers = [(1, "Fezzik".to_string())].into();
+        second.interviews[0].interview.sections[1].apply(CodeId(0), "alice", 0);
+
+        let merged = merge(&[("a".to_string(), first), ("b".to_string(), second)]);
+
+        assert_eq!(
+            merged.conflicts,
+            [
+                Conflict::Speaker {
+                    interview: "interview".to_string(),
+                    section: None,
+                    file: "b".to_string(),
+                    kept: "Inigo".to_string(),
+                    other: "Fezzik".to_string(),
+                },
+                Conflict::Text {
+                    interview: "interview".to_string(),
+                    section: 0,
+                    file: "b".to_string(),
+                    kept: "hello there".to_string(),
+                    other: "hello you".to_string(),
+                },
+                Conflict::Codes {
+                    interview: "interview".to_string(),
+                    section: 0,
+                    file: "b".to_string(),
+                    coder: Some("alice".to_string()),
+                    kept: vec!["anger".to_string()],
+                    other: vec![],
+                },
+                Conflict::Codes {
+                    interview: "interview".to_string(),
+                    section: 1,
+                    file: "b".to_string(),
+                    coder: Some("alice".to_string()),
+                    kept: vec![],
+                    other: vec!["anger".to_string()],
+                },
+            ]
+        );
+        let sections = &merged.interviews[0].interview.sections;
+        assert_eq!(sections[0].text, "hello there");
+        assert!(sections[1].coded_by(CodeId(0), Some("alice")));
+    }
+
+    #[test]
+    fn coded_excerpts_follow_a_text_correction() {
+        let span = |start, end| CodedSpan {
+            start,
+            end,
+            code: CodeId(0),
+        };
+        let mut uncorrected = section("um hello there you", &[]);
+        uncorrected.spans = [span(0, 2), span(15, 18)].into();
+        let mut corrected = section("hello there you", &[]);
+        corrected.original_text = Some("um hello there you".to_string());
+        corrected.spans = [span(6, 11)].into();
+        let first = project(&[(0, "anger")], vec![uncorrected]);
+        let second = project(&[(0, "anger")], vec![corrected]);
+
+        let merged = merge(&[("a".to_string(), first), ("b".to_string(), second)]);
+
+        assert_eq!(
+            merged.conflicts,
+            [Conflict::Excerpts {
+                interview: "interview".to_string(),
+                section: 0,
+                file: "b".to_string(),
+                dropped: 1,
+            }]
+        );
+        let section = &merged.interviews[0].interview.sections[0];
+        assert_eq!(section.text, "hello there you");
+        let excerpts = section
+            .spans
+            .iter()
+            .map(|span| section.excerpt(span))
+            .collect::<Vec<_>>();
+        assert_eq!(excerpts, ["there", "you"]);
+    }
+
+    #[test]
+    fn uncorrected_text_takes_the_correction() {
+        let mut corrected = section("hello there", &[]);
+        corrected.original_text = Some("hello".to_string());
+        let first = project(&[], vec![section("hello", &[])]);
+        let second = project(&[], vec![corrected]);
+
+        let merged = merge(&[("a".to_string(), first), ("b".to_string(), second)]);
+
+        assert_eq!(merged.conflicts, []);
+        let section = &merged.interviews[0].interview.sections[0];
+        assert_eq!(section.text, "hello there");
+        assert_eq!(section.original_text.as_deref(), Some("hello"));
+    }
+}
@@ -0,0 +1,731 @@
+//! Agreement between two coders who coded the same interviews, as needed to report inter-rater
+//! reliability. Only the codes of whole sections are compared.
+
+use crate::app::consensus::Comparison;
+use crate::app::export::{self, ExportReport};
+use crate::app::file_upload::{self, UploadedFile};
+use crate::app::notifications::Notifications;
+use crate::app::project::{self, ProjectError};
+use crate::app::{Code, Interview, QualityQualitativeCoding};
+use egui::Ui;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+
+/// The codes one coder applied to each section of some interviews. Codes are referred to by name
+/// so that codings made against different copies of a codebook can be compared.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Coding {
+    /// the code names of every section by interview name
+    pub interviews: BTreeMap<String, Vec<BTreeSet<String>>>,
+    /// names shared by several interviews, which are left out as they can't be told apart
+    pub duplicated: BTreeSet<String>,
+}
+
+#[derive(Debug)]
+pub enum CodingError {
+    Project(ProjectError),
+    Csv(csv::Error),
+    /// the csv was not exported by qqc, or not in a format with the codes of every section
+    UnknownCsv,
+    /// the file is neither a project nor a csv
+    UnknownFile,
+}
+
+impl Display for CodingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CodingError::Project(err) => write!(f, "{}", err),
+            CodingError::Csv(err) => write!(f, "not a valid csv: {}", err),
+            CodingError::UnknownCsv => write!(
+                f,
+                "expected an interview csv exported by qqc with an interview column"
+            ),
+            CodingError::UnknownFile => write!(
+                f,
+                "expected a .{} project or an exported interview .csv",
+                project::EXTENSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CodingError {}
+
+impl From<ProjectError> for CodingError {
+    fn from(err: ProjectError) -> Self {
+        CodingError::Project(err)
+    }
+}
+
+impl From<csv::Error> for CodingError {
+    fn from(err: csv::Error) -> Self {
+        CodingError::Csv(err)
+    }
+}
+
+impl Coding {
+    /// the coding of `interviews` with the names of `codes`
+    pub fn from_interviews<'a>(
+        interviews: impl IntoIterator<Item = &'a Interview>,
+        codes: &[Code],
+    ) -> Coding {
+        let mut coding = Coding::default();
+        for interview in interviews {
+            let sections = interview
+                .sections
+                .iter()
+                .map(|section| {
+                    section
+                        .codes
+                        .iter()
+                        .filter_map(|id| Code::name_of(codes, *id))
+                        .map(String::from)
+                        .collect()
+                })
+                .collect();
+            if coding.interviews.contains_key(&interview.name) {
+                coding.duplicated.insert(interview.name.clone());
+            }
+            coding.interviews.insert(interview.name.clone(), sections);
+        }
+        coding.drop_duplicated();
+        coding
+    }
+
+    fn drop_duplicated(&mut self) {
+        for name in &self.duplicated {
+            self.interviews.remove(name);
+        }
+    }
+
+    /// Reads a project file or an interview csv in any of the formats qqc exports.
+    pub fn from_file(UploadedFile { name, bytes }: &UploadedFile) -> Result<Coding, CodingError> {
+        let extension = name.rsplit_once('.').map(|(_, extension)| extension);
+        match extension {
+            Some(extension) if extension == project::EXTENSION || extension == "json" => {
+                let json = String::from_utf8_lossy(bytes);
+                let project = project::from_json(&json)?;
+                Ok(Coding::from_interviews(
+                    project.interviews.iter().map(|swiper| &swiper.interview),
+                    &project.codes,
+                ))
+            }
+            Some("csv") => Coding::from_csv(bytes),
+            _ => Err(CodingError::UnknownFile),
+        }
+    }
+
+    /// Reads an interview csv. Long csvs have a `section` and a `code` column, code matrices have a
+    /// `section` column and a 0/1 column per code and the rest have a row per section with the codes
+    /// in `code0`, `code1` and so on.
+    fn from_csv(bytes: &[u8]) -> Result<Coding, CodingError> {
+        const NOT_CODES: [&str; 10] = [
+            "interview",
+            "section",
+            "speaker",
+            "start",
+            "end",
+            "text",
+            "original_text",
+            "memo",
+            "interview_memo",
+            "coded_by",
+        ];
+        let mut reader = csv::Reader::from_reader(bytes);
+        let headers = reader.headers()?.clone();
+        let column = |name: &str| headers.iter().position(|header| header == name);
+        let interview_column = column("interview").ok_or(CodingError::UnknownCsv)?;
+        let section_column = column("section");
+        let code_column = column("code");
+        let excerpt_column = column("excerpt");
+        let wide_code_columns = headers
+            .iter()
+            .enumerate()
+            .filter(|(_, header)| {
+                header
+                    .strip_prefix("code")
+                    .map_or(false, |number| number.parse::<usize>().is_ok())
+            })
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        let mut coding = Coding::default();
+        // the interview and section of the previous row
+        let mut previous: Option<(String, usize)> = None;
+        for record in reader.records() {
+            let record = record?;
+            let field = |index: usize| record.get(index).unwrap_or_default();
+            let name = field(interview_column).to_string();
+            let index = match section_column {
+                Some(column) => Some(field(column).parse().map_err(|_| CodingError::UnknownCsv)?),
+                None => None,
+            };
+            // interviews are exported one after the other with their sections in order, so
+            // an interview coming back or starting over is another interview with the same name
+            let duplicated = match &previous {
+                Some((previous, _)) if *previous != name => coding.interviews.contains_key(&name),
+                Some((_, previous)) => index.map_or(false, |index| index < *previous),
+                None => false,
+            };
+            if duplicated {
+                coding.duplicated.insert(name.clone());
+            }
+            let sections = coding.interviews.entry(name.clone()).or_default();
+            let index = index.unwrap_or(sections.len());
+            previous = Some((name, index));
+            if sections.len() <= index {
+                sections.resize_with(index + 1, BTreeSet::new);
+            }
+            let codes = &mut sections[index];
+            match (section_column, code_column) {
+                (Some(_), Some(code)) => {
+                    // rows with an excerpt code part of the section rather than all of it
+                    let excerpt = excerpt_column.map_or("", field);
+                    if !field(code).is_empty() && excerpt.is_empty() {
+                        codes.insert(field(code).to_string());
+                    }
+                }
+                (Some(_), None) => {
+                    for (index, header) in headers.iter().enumerate() {
+                        if !NOT_CODES.contains(&header) && field(index) == "1" {
+                            codes.insert(header.to_string());
+                        }
+                    }
+                }
+                (None, _) if !wide_code_columns.is_empty() => {
+                    for index in &wide_code_columns {
+                        if !field(*index).is_empty() {
+                            codes.insert(field(*index).to_string());
+                        }
+                    }
+                }
+                (None, _) => return Err(CodingError::UnknownCsv),
+            }
+        }
+        coding.drop_duplicated();
+        Ok(coding)
+    }
+}
+
+/// How often two coders agreed on applying a code, or on every code when pooled, counted over
+/// sections.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Agreement {
+    /// sections both coders coded with the code
+    pub both: usize,
+    /// sections only the first coder coded with the code
+    pub first_only: usize,
+    /// sections only the second coder coded with the code
+    pub second_only: usize,
+    /// sections neither coder coded with the code
+    pub neither: usize,
+}
+
+impl Agreement {
+    fn add(&mut self, first: bool, second: bool) {
+        match (first, second) {
+            (true, true) => self.both += 1,
+            (true, false) => self.first_only += 1,
+            (false, true) => self.second_only += 1,
+            (false, false) => self.neither += 1,
+        }
+    }
+
+    fn plus(self, other: Agreement) -> Agreement {
+        Agreement {
+            both: self.both + other.both,
+            first_only: self.first_only + other.first_only,
+            second_only: self.second_only + other.second_only,
+            neither: self.neither + other.neither,
+        }
+    }
+
+    pub fn units(&self) -> usize {
+        self.both + self.first_only + self.second_only + self.neither
+    }
+
+    fn disagreements(&self) -> usize {
+        self.first_only + self.second_only
+    }
+
+    /// the share of sections the coders agreed on
+    pub fn percent_agreement(&self) -> Option<f64> {
+        let units = self.units() as f64;
+        (units > 0.0).then(|| (units - self.disagreements() as f64) / units * 100.0)
+    }
+
+    /// Agreement corrected for the agreement expected by chance given how often each coder used
+    /// the code. Undefined when both coders always, or never, used it.
+    pub fn cohens_kappa(&self) -> Option<f64> {
+        let units = self.units() as f64;
+        let observed = (self.both + self.neither) as f64 / units;
+        let first = (self.both + self.first_only) as f64 / units;
+        let second = (self.both + self.second_only) as f64 / units;
+        let expected = first * second + (1.0 - first) * (1.0 - second);
+        (units > 0.0 && expected < 1.0).then(|| (observed - expected) / (1.0 - expected))
+    }
+
+    /// Krippendorff's alpha for nominal data from two coders without missing values. Undefined
+    /// when the code was always, or never, used.
+    pub fn krippendorffs_alpha(&self) -> Option<f64> {
+        let values = 2 * self.units();
+        let coded = 2 * self.both + self.disagreements();
+        let uncoded = values - coded;
+        (coded > 0 && uncoded > 0).then(|| {
+            1.0 - (values - 1) as f64 * self.disagreements() as f64
+                / (coded as f64 * uncoded as f64)
+        })
+    }
+}
+
+/// The agreement between two codings.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ReliabilityReport {
+    /// the agreement on each code, in the order of the first coding's use of them
+    pub codes: Vec<(String, Agreement)>,
+    /// the agreement pooled over every code and section
+    pub overall: Agreement,
+    /// the number of sections compared
+    pub sections: usize,
+    /// interviews that could not be compared, and why
+    pub skipped: Vec<String>,
+}
+
+/// Compares the codes of every section of the interviews in both codings, matching interviews
+/// by name and sections by position. With `only_double_coded` sections only one of the coders
+/// coded are left out, as when a second coder codes a sample of each interview.
+pub fn compare(first: &Coding, second: &Coding, only_double_coded: bool) -> ReliabilityReport {
+    let mut report = ReliabilityReport::default();
+    let mut units = Vec::new();
+    let display = |name: &str| {
+        if name.is_empty() {
+            "unnamed interview".to_string()
+        } else {
+            name.to_string()
+        }
+    };
+    for (name, first_sections) in &first.interviews {
+        match second.interviews.get(name) {
+            None if second.duplicated.contains(name) => {}
+            None => report
+                .skipped
+                .push(format!("{} is only in the first coding", display(name))),
+            Some(second_sections) if second_sections.len() != first_sections.len() => {
+                report.skipped.push(format!(
+                    "{} has {} sections in the first coding but {} in the second",
+                    display(name),
+                    first_sections.len(),
+                    second_sections.len()
+                ))
+            }
+            Some(second_sections) => {
+                units.extend(first_sections.iter().zip(second_sections).filter(
+                    |(first, second)| !only_double_coded || !first.is_empty() && !second.is_empty(),
+                ))
+            }
+        }
+    }
+    for name in first.duplicated.union(&second.duplicated) {
+        report.skipped.push(format!(
+            "{} is the name of more than one interview",
+            display(name)
+        ));
+    }
+    for name in second.interviews.keys() {
+        if !first.interviews.contains_key(name) && !first.duplicated.contains(name) {
+            report
+                .skipped
+                .push(format!("{} is only in the second coding", display(name)));
+        }
+    }
+    let mut codes: Vec<&String> = Vec::new();
+    for (first, second) in &units {
+        for code in first.iter().chain(second.iter()) {
+            if !codes.contains(&code) {
+                codes.push(code);
+            }
+        }
+    }
+    report.sections = units.len();
+    report.codes = codes
+        .into_iter()
+        .map(|code| {
+            let mut agreement = Agreement::default();
+            for (first, second) in &units {
+                agreement.add(first.contains(code), second.contains(code));
+            }
+            (code.clone(), agreement)
+        })
+        .collect();
+    report.overall = report
+        .codes
+        .iter()
+        .fold(Agreement::default(), |overall, (_, agreement)| {
+            overall.plus(*agreement)
+        });
+    report
+}
+
+/// A row of the exported report.
+#[derive(serde::Serialize, Debug)]
+struct CsvAgreement<'a> {
+    code: &'a str,
+    sections: usize,
+    both: usize,
+    first_only: usize,
+    second_only: usize,
+    neither: usize,
+    percent_agreement: Option<f64>,
+    cohens_kappa: Option<f64>,
+    krippendorffs_alpha: Option<f64>,
+}
+
+impl<'a> CsvAgreement<'a> {
+    fn new(code: &'a str, agreement: &Agreement) -> Self {
+        CsvAgreement {
+            code,
+            sections: agreement.units(),
+            both: agreement.both,
+            first_only: agreement.first_only,
+            second_only: agreement.second_only,
+            neither: agreement.neither,
+            percent_agreement: agreement.percent_agreement(),
+            cohens_kappa: agreement.cohens_kappa(),
+            krippendorffs_alpha: agreement.krippendorffs_alpha(),
+        }
+    }
+}
+
+/// A coding the user loaded, along with where to receive the file from.
+#[derive(Debug)]
+struct CodingSlot {
+    /// the name of the file or project the coding came from
+    coding: Option<(String, Coding)>,
+    channel: (Sender<UploadedFile>, Receiver<UploadedFile>),
+}
+
+impl Default for CodingSlot {
+    fn default() -> Self {
+        Self {
+            coding: None,
+            channel: channel(),
+        }
+    }
+}
+
+/// The two codings being compared and how.
+#[derive(Debug, Default)]
+pub struct Reliability {
+    first: CodingSlot,
+    second: CodingSlot,
+    only_double_coded: bool,
+    export_report: ExportReport,
+}
+
+impl Reliability {
+    /// Reads any codings the user has uploaded.
+    pub fn try_update(&mut self, notifications: &mut Notifications) {
+        for slot in [&mut self.first, &mut self.second] {
+            match slot.channel.1.try_recv() {
+                Ok(file) => match Coding::from_file(&file) {
+                    Ok(coding) => slot.coding = Some((file.name, coding)),
+                    Err(err) => {
+                        notifications.error(&format!("could not read coding {}", file.name), err)
+                    }
+                },
+                Err(TryRecvError::Empty) => { /* no file has been uploaded yet - no problem! */ }
+                Err(TryRecvError::Disconnected) => {
+                    panic!("impossible to upload files. sender has been dropped.")
+                }
+            }
+        }
+    }
+
+    /// Compares `coder`'s coding of the project with the other coder's, if there is only one.
+    pub fn compare_coder(&mut self, interviews: &[&Interview], codes: &[Code], coder: &str) {
+        let others = coders(interviews)
+            .into_iter()
+            .filter(|other| other != coder)
+            .collect::<Vec<_>>();
+        self.first.coding = Some(coding_of(interviews, codes, coder));
+        self.second.coding = match others.as_slice() {
+            [other] => Some(coding_of(interviews, codes, other)),
+            _ => None,
+        };
+    }
+
+    /// Shows the agreement between the codings, returning them as a comparison if the user wants to
+    /// reconcile their disagreements.
+    pub fn ui(
+        &mut self,
+        ui: &mut Ui,
+        interviews: &[&Interview],
+        codes: &[Code],
+    ) -> Option<Comparison> {
+        let Reliability {
+            first,
+            second,
+            only_double_coded,
+            export_report,
+        } = self;
+        let coders = coders(interviews);
+        for (label, slot) in [
+            ("first coding", &mut *first),
+            ("second coding", &mut *second),
+        ] {
+            ui.horizontal(|ui| {
+                ui.label(label);
+                match &slot.coding {
+                    Some((name, _)) => ui.strong(name),
+                    None => ui.weak("none"),
+                };
+                if ui
+                    .button("load")
+                    .on_hover_text("a project file or an exported interview csv")
+                    .clicked()
+                {
+                    file_upload::open_upload_dialog(
+                        slot.channel.0.clone(),
+                        ("coding", &[project::EXTENSION, "csv"]),
+                    );
+                }
+                if ui.button("this project").clicked() {
+                    let coding = Coding::from_interviews(interviews.iter().copied(), codes);
+                    slot.coding = Some(("this project".to_string(), coding));
+                }
+                for coder in &coders {
+                    if ui
+                        .button(coder)
+                        .on_hover_text("the codes this coder applied in this project")
+                        .clicked()
+                    {
+                        slot.coding = Some(coding_of(interviews, codes, coder));
+                    }
+                }
+            });
+        }
+        ui.checkbox(only_double_coded, "only compare sections both coders coded")
+            .on_hover_text(
+                "leave out sections either coder left without codes, as when a second coder codes \
+                a sample. Sections that were deliberately left uncoded are left out as well.",
+            );
+        let (first, second) = match (&first.coding, &second.coding) {
+            (Some(first), Some(second)) => (first, second),
+            _ => {
+                ui.label("load two codings of the same interviews to compare them");
+                return None;
+            }
+        };
+        let report = compare(&first.1, &second.1, *only_double_coded);
+        report_ui(ui, &report);
+        export::export_rows(
+            ui,
+            "reliability.csv",
+            export_report,
+            report
+                .codes
+                .iter()
+                .map(|(code, agreement)| CsvAgreement::new(code, agreement))
+                .chain(std::iter::once(CsvAgreement::new(
+                    "overall",
+                    &report.overall,
+                ))),
+        );
+        ui.button("reconcile disagreements")
+            .on_hover_text(
+                "step through the sections the codings differ on, writing the codes agreed on to \
+                 the interviews of this project",
+            )
+            .clicked()
+            .then(|| Comparison::new(first.clone(), second.clone()))
+    }
+}
+
+/// everyone who applied a code in `interviews`
+fn coders(interviews: &[&Interview]) -> Vec<String> {
+    let mut coders = interviews
+        .iter()
+        .flat_map(|interview| QualityQualitativeCoding::coders(&interview.sections, ""))
+        .collect::<Vec<_>>();
+    coders.sort_unstable();
+    coders.dedup();
+    coders
+}
+
+/// the coding `coder` made of the interviews of this project, named after them
+fn coding_of(interviews: &[&Interview], codes: &[Code], coder: &str) -> (String, Coding) {
+    let coded = interviews
+        .iter()
+        .map(|interview| interview.as_coded_by(coder))
+        .collect::<Vec<_>>();
+    (coder.to_string(), Coding::from_interviews(&coded, codes))
+}
+
+fn report_ui(ui: &mut Ui, report: &ReliabilityReport) {
+    for skipped in &report.skipped {
+        ui.colored_label(ui.visuals().warn_fg_color, skipped);
+    }
+    ui.label(format!("{} sections compared", report.sections));
+    let format =
+        |value: Option<f64>| value.map_or("-".to_string(), |value| format!("{:.2}", value));
+    egui::ScrollArea::vertical()
+        .max_height(400.0)
+        .show(ui, |ui| {
+            egui::Grid::new("reliability").striped(true).show(ui, |ui| {
+                for header in ["code", "% agreement", "Cohen's κ", "Krippendorff's α"] {
+                    ui.strong(header);
+                }
+                ui.end_row();
+                let overall = ("overall".to_string(), report.overall);
+                for (code, agreement) in report.codes.iter().chain(std::iter::once(&overall)) {
+                    ui.label(code);
+                    ui.label(format(agreement.percent_agreement()));
+                    ui.label(format(agreement.cohens_kappa()));
+                    ui.label(format(agreement.krippendorffs_alpha()));
+                    ui.end_row();
+                }
+            });
+        });
+}
+
+/// a coding of one interview, named "interview", with the codes of each of its sections
+#[cfg(test)]
+pub(crate) fn coding(sections: &[&[&str]]) -> Coding {
+    Coding {
+        interviews: BTreeMap::from([(
+            "interview".to_string(),
+            sections
+                .iter()
+                .map(|codes| codes.iter().map(|code| code.to_string()).collect())
+                .collect(),
+        )]),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round(value: Option<f64>) -> Option<f64> {
+        value.map(|value| (value * 1000.0).round() / 1000.0)
+    }
+
+    #[test]
+    fn agreement_matches_worked_examples() {
+        // 10 sections: both coded 4, only the first 1, only the second 1, neither 4
+        let agreement = Agreement {
+            both: 4,
+            first_only: 1,
+            second_only: 1,
+            neither: 4,
+        };
+
+        assert_eq!(round(agreement.percent_agreement()), Some(80.0));
+        assert_eq!(round(agreement.cohens_kappa()), Some(0.6));
+        // 1 - (20 - 1) * 2 / (10 * 10)
+        assert_eq!(round(agreement.krippendorffs_alpha()), Some(0.62));
+    }
+
+    #[test]
+    fn agreement_is_undefined_without_variation() {
+        let agreement = Agreement {
+            both: 3,
+            ..Default::default()
+        };
+
+        assert_eq!(agreement.percent_agreement(), Some(100.0));
+        assert_eq!(agreement.cohens_kappa(), None);
+        assert_eq!(agreement.krippendorffs_alpha(), None);
+    }
+
+    #[test]
+    fn compares_every_code_of_double_coded_sections() {
+        let first = coding(&[&["anger"], &["anger", "calm"], &[], &["calm"]]);
+        let second = coding(&[&["anger"], &["calm"], &["anger"], &[]]);
+
+        let report = compare(&first, &second, true);
+
+        assert_eq!(report.sections, 2);
+        assert_eq!(
+            report.codes,
+            [
+                (
+                    "anger".to_string(),
+                    Agreement {
+                        both: 1,
+                        first_only: 1,
+                        ..Default::default()
+                    }
+                ),
+                (
+                    "calm".to_string(),
+                    Agreement {
+                        both: 1,
+                        neither: 1,
+                        ..Default::default()
+                    }
+                )
+            ]
+        );
+        assert_eq!(report.overall.units(), 4);
+        assert_eq!(compare(&first, &second, false).sections, 4);
+    }
+
+    #[test]
+    fn reads_codings_from_every_export_format() {
+        let expected = coding(&[&["anger", "calm"], &[]]);
+        let wide = "interview,speaker,start,end,text,code0,code1,code2,excerpts
+interview,a,,,hi,anger,calm,,
+interview,b,,,bye,,,,
+";
+        let long = "interview,section,speaker,start,end,text,code,excerpt
+interview,0,a,,,hi,anger,
+interview,0,a,,,hi,calm,
+interview,0,a,,,hi,rage,hi
+interview,1,b,,,bye,,
+";
+        let matrix =
+            "interview,section,speaker,start,end,text,memo,interview_memo,coded_by,anger,calm
+interview,0,a,,,hi,,1,1,1,1
+interview,1,b,,,bye,,1,1,0,0
+";
+
+        for csv in [wide, long, matrix] {
+            assert_eq!(Coding::from_csv(csv.as_bytes()).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn interviews_sharing_a_name_are_skipped() {
+        let interview = |name: &str| Interview {
+            name: name.to_string(),
+            sections: vec![Default::default()],
+            ..Default::default()
+        };
+        let interviews = [interview("a"), interview("b"), interview("a")];
+        let project = Coding::from_interviews(&interviews, &[]);
+        let long = "interview,section,speaker,start,end,text,code,excerpt
+a,0,,,,hi,,
+b,0,,,,hi,,
+a,0,,,,hi,,
+";
+        let matrix = "interview,section,speaker,start,end,text
+a,0,,,,hi
+a,1,,,,hi
+a,0,,,,hi
+b,0,,,,hi
+";
+
+        for coding in [
+            project,
+            Coding::from_csv(long.as_bytes()).unwrap(),
+            Coding::from_csv(matrix.as_bytes()).unwrap(),
+        ] {
+            assert_eq!(coding.interviews.keys().collect::<Vec<_>>(), ["b"]);
+            let report = compare(&coding, &coding, false);
+            assert_eq!(report.sections, 1);
+            assert_eq!(report.skipped, ["a is the name of more than one interview"]);
+        }
+    }
+}
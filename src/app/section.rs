@@ -1,33 +1,301 @@
-use crate::app::Section;
+use crate::app::consensus::Review;
+use crate::app::{Code, Section};
+use egui::text::{LayoutJob, TextFormat};
+use egui::widgets::text_edit::TextEditState;
+use egui::{Color32, Stroke};
+use std::ops::Range;
 
-pub fn primary_section<'a>(section: &'a Section, speaker: &'a str) -> impl egui::Widget + 'a {
-    move |ui: &mut egui::Ui| primary_section_ui(ui, section, speaker)
+/// The section being coded. Its text can be selected to code part of it, coded parts are
+/// highlighted in the color of their code and so are words transcribed with a confidence below
+/// `confidence_threshold`.
+pub fn primary_section<'a>(
+    section: &'a Section,
+    codes: &'a [Code],
+    speaker: &'a str,
+    confidence_threshold: f64,
+    text_id: egui::Id,
+    coder: Option<&'a str>,
+) -> impl egui::Widget + 'a {
+    move |ui: &mut egui::Ui| {
+        primary_section_ui(
+            ui,
+            section,
+            codes,
+            speaker,
+            confidence_threshold,
+            text_id,
+            coder,
+        )
+    }
 }
 
-pub fn secondary_section<'a>(section: &'a Section, speaker: &'a str) -> impl egui::Widget + 'a {
-    move |ui: &mut egui::Ui| secondary_section_ui(ui, section, speaker)
+/// a section around the one being coded, with a chip for each of its codes
+pub fn secondary_section<'a>(
+    section: &'a Section,
+    codes: &'a [Code],
+    speaker: &'a str,
+    coder: Option<&'a str>,
+) -> impl egui::Widget + 'a {
+    move |ui: &mut egui::Ui| secondary_section_ui(ui, section, codes, speaker, coder)
+}
+
+/// the id of the text of the primary section, which is where its selection is kept
+pub fn primary_text_id(interview: usize, section: usize) -> egui::Id {
+    egui::Id::new(("primary section text", interview, section))
+}
+
+/// the characters of the primary section's text that are selected, if any
+pub fn selection(
+    ctx: &egui::Context,
+    text_id: egui::Id,
+    section: &Section,
+) -> Option<(usize, usize)> {
+    let [start, end] = TextEditState::load(ctx, text_id)?.ccursor_range()?.sorted();
+    let length = section.text.chars().count();
+    let (start, end) = (start.index.min(length), end.index.min(length));
+    (start < end).then(|| (start, end))
+}
+
+pub fn clear_selection(ctx: &egui::Context, text_id: egui::Id) {
+    if let Some(mut state) = TextEditState::load(ctx, text_id) {
+        state.set_ccursor_range(None);
+        state.store(ctx, text_id);
+    }
 }
 
 fn primary_section_ui(
     ui: &mut egui::Ui,
-    Section { text, .. }: &Section,
+    section: &Section,
+    codes: &[Code],
     speaker: &str,
+    confidence_threshold: f64,
+    text_id: egui::Id,
+    coder: Option<&str>,
 ) -> egui::Response {
     ui.vertical(|ui| {
-        ui.label(speaker);
-        ui.label(text);
+        ui.horizontal(|ui| {
+            ui.label(speaker);
+            if let Some(time_range) = time_range(section) {
+                ui.weak(time_range);
+            }
+            if let Some(original) = &section.original_text {
+                ui.weak("corrected")
+                    .on_hover_text(format!("transcribed as \"{}\"", original));
+            }
+            memo_marker(ui, section);
+            review_marker(ui, section);
+            code_chips(ui, section, codes, coder);
+        });
+        let mut layouter = |ui: &egui::Ui, _: &str, wrap_width: f32| {
+            let mut job = highlight(ui, section, codes, confidence_threshold);
+            job.wrap.max_width = wrap_width;
+            ui.fonts().layout_job(job)
+        };
+        // a read only text edit so that the text can be selected
+        egui::TextEdit::multiline(&mut section.text.as_str())
+            .id(text_id)
+            .frame(false)
+            .desired_rows(1)
+            .desired_width(f32::INFINITY)
+            .layouter(&mut layouter)
+            .show(ui);
     })
     .response
 }
 
+/// marks a section that has a memo, which is shown on hover
+fn memo_marker(ui: &mut egui::Ui, section: &Section) {
+    if !section.memo.is_empty() {
+        ui.weak("memo").on_hover_text(&section.memo);
+    }
+}
+
+/// marks a section the coders have reviewed together
+fn review_marker(ui: &mut egui::Ui, section: &Section) {
+    match section.review {
+        Some(review @ Review::Unresolved) => {
+            ui.colored_label(ui.visuals().warn_fg_color, review.label());
+        }
+        Some(review) => {
+            ui.weak(review.label());
+        }
+        None => {}
+    }
+}
+
+/// a chip in the color of each code the section, or part of it, was coded with. Showing one
+/// `coder`'s codes leaves out coded excerpts, which are not recorded per coder.
+fn code_chips(ui: &mut egui::Ui, section: &Section, codes: &[Code], coder: Option<&str>) {
+    let coded = |code: &&Code| match coder {
+        None => section.has_code(code.id),
+        Some(_) => section.coded_by(code.id, coder),
+    };
+    for code in codes.iter().filter(coded) {
+        let chip = egui::RichText::new(&code.name)
+            .small()
+            .background_color(highlight_color(code.color().into()));
+        let coders = section.coders_of(code.id);
+        let hover_text = if coders.is_empty() {
+            code.description.clone()
+        } else {
+            format!("{}\ncoded by {}", code.description, coders.join(", "))
+        };
+        ui.label(chip).on_hover_text(hover_text.trim());
+    }
+}
+
+/// the color of a code made translucent, so text stays readable on light and dark backgrounds
+fn highlight_color(color: Color32) -> Color32 {
+    color.linear_multiply(0.35)
+}
+
+/// the text of `section` with coded spans and uncertain words highlighted
+fn highlight(
+    ui: &egui::Ui,
+    section: &Section,
+    codes: &[Code],
+    confidence_threshold: f64,
+) -> LayoutJob {
+    let format = TextFormat {
+        font_id: egui::TextStyle::Body.resolve(ui.style()),
+        color: ui.visuals().text_color(),
+        ..Default::default()
+    };
+    let uncertain = uncertain_words(section, confidence_threshold);
+    let format_of = |position: usize| {
+        // where spans overlap the first one's code is shown
+        let coded = section
+            .spans
+            .iter()
+            .find(|span| span.start <= position && position < span.end);
+        let uncertain = uncertain.iter().any(|word| word.contains(&position));
+        TextFormat {
+            background: coded.map_or(Color32::TRANSPARENT, |span| {
+                highlight_color(Code::color_of(codes, span.code))
+            }),
+            underline: if uncertain {
+                Stroke::new(2.0, ui.visuals().warn_fg_color)
+            } else {
+                Stroke::none()
+            },
+            ..format.clone()
+        }
+    };
+    let text = &section.text;
+    let mut job = LayoutJob::default();
+    let mut run = (0, format_of(0));
+    for (position, (byte, _)) in text.char_indices().enumerate() {
+        let format = format_of(position);
+        if format != run.1 {
+            job.append(
+                &text[run.0..byte],
+                0.0,
+                std::mem::replace(&mut run, (byte, format)).1,
+            );
+        }
+    }
+    job.append(&text[run.0..], 0.0, run.1);
+    job
+}
+
+/// The positions of the characters of words transcribed with a confidence below
+/// `confidence_threshold`. The words are of the transcript so are no use once it has been
+/// corrected or divided differently.
+fn uncertain_words(section: &Section, confidence_threshold: f64) -> Vec<Range<usize>> {
+    if section.original_text.is_some() {
+        return Vec::new();
+    }
+    let words = word_positions(&section.text);
+    if words.len() != section.words.len() {
+        return Vec::new();
+    }
+    words
+        .into_iter()
+        .zip(&section.words)
+        .filter(|(_, word)| word.is_uncertain(confidence_threshold))
+        .map(|(position, _)| position)
+        .collect()
+}
+
+/// the positions of the characters of every whitespace separated word in `text`
+fn word_positions(text: &str) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (position, char) in text.chars().chain(std::iter::once(' ')).enumerate() {
+        match (start, char.is_whitespace()) {
+            (None, false) => start = Some(position),
+            (Some(word_start), true) => {
+                words.push(word_start..position);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
 fn secondary_section_ui(
     ui: &mut egui::Ui,
-    Section { text, .. }: &Section,
+    section: &Section,
+    codes: &[Code],
     speaker: &str,
+    coder: Option<&str>,
 ) -> egui::Response {
     ui.vertical(|ui| {
-        ui.weak(speaker);
-        ui.weak(text);
+        ui.horizontal(|ui| {
+            ui.weak(speaker);
+            if let Some(time_range) = time_range(section) {
+                ui.weak(time_range);
+            }
+            memo_marker(ui, section);
+            review_marker(ui, section);
+            code_chips(ui, section, codes, coder);
+        });
+        ui.weak(&section.text);
     })
     .response
 }
+
+/// when in the recording the section was said, if the transcript told us
+fn time_range(Section { start, end, .. }: &Section) -> Option<String> {
+    match (start, end) {
+        (Some(start), Some(end)) => Some(format!(
+            "{} - {}",
+            format_timestamp(*start),
+            format_timestamp(*end)
+        )),
+        (Some(start), None) => Some(format_timestamp(*start)),
+        (None, Some(end)) => Some(format!("- {}", format_timestamp(*end))),
+        (None, None) => None,
+    }
+}
+
+/// formats seconds as `hh:mm:ss.mmm`
+pub fn format_timestamp(seconds: f64) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0.0), "00:00:00.000");
+        assert_eq!(format_timestamp(3723.004), "01:02:03.004");
+        assert_eq!(format_timestamp(59.9996), "00:01:00.000");
+    }
+
+    #[test]
+    fn test_word_positions() {
+        assert_eq!(word_positions(" Okay,  sure "), [1..6, 8..12]);
+        assert_eq!(word_positions(""), []);
+    }
+}